@@ -1,4 +1,5 @@
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg(nightly)");
     if rustc_version::Channel::Nightly == rustc_version::version_meta().unwrap().channel {
         println!("cargo:rustc-cfg=nightly");
     }
@@ -25,8 +25,8 @@ impl std::error::Error for Error {}
 impl std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Simple(message) => write!(fmt, "{}", message),
-            Self::WithError(message, error) => write!(fmt, "{}: {}", message, error),
+            Self::Simple(message) => write!(fmt, "{message}"),
+            Self::WithError(message, error) => write!(fmt, "{message}: {error}"),
         }
     }
 }
@@ -1,163 +1,736 @@
+mod cages;
+mod canonical;
+mod check;
+mod constraint;
+mod dlx;
+mod hint;
+mod history;
+mod logic;
 mod ops;
+mod regions;
+mod samurai;
+mod solver;
 mod transform;
+mod variant;
+
+pub use cages::{Cage, Cages};
+pub use check::Check;
+pub use constraint::Constraint;
+pub use hint::{Hint, Tier};
+pub use history::History;
+pub use logic::Trace;
+#[cfg(test)]
+pub use regions::jigsaw_labels;
+pub use regions::Regions;
+pub use samurai::Samurai;
+pub use solver::{Backend, Solver};
+pub use transform::Transformation;
+pub use variant::Variant;
 
-use crate::index::{ColumnIndexer, SectorIndexer};
+use std::sync::Arc;
 
-type Board = [Token; 81];
+use crate::error::Error;
+use crate::index::{BoardIndexer, ColumnIndexer, RowIndexer};
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Game {
-    board: [Token; 81],
-    columns: [[Token; 9]; 9],
-    sectors: [[Token; 9]; 9],
+    size: Size,
+    regions: Regions,
+    cages: Option<Cages>,
+    variants: Vec<Variant>,
+    constraints: Vec<Arc<dyn Constraint>>,
+    board: Vec<Token>,
+    // Cells that held a token when the puzzle was made, shared between the copies like the regions
+    givens: Arc<[bool]>,
+    // Pencil marks of the player, left empty until the first one so that the solvers do not copy
+    // them around
+    marks: Vec<Candidates>,
+    rows: Vec<u32>,
+    columns: Vec<u32>,
+    sectors: Vec<u32>,
 }
 
 impl Game {
-    pub fn new_empty() -> Self {
-        Self::from([Token::None; 81])
+    pub fn new_empty(size: Size) -> Self {
+        Self::with_tokens(size, vec![Token::None; size.cells()])
+    }
+
+    pub fn new_solved(size: Size, rng: &mut impl rand::Rng) -> Self {
+        ops::generate_solved(size, rng)
+    }
+
+    pub fn with_tokens(size: Size, board: Vec<Token>) -> Self {
+        Self::with_regions(Regions::standard(size), board)
+    }
+
+    pub fn with_regions(regions: Regions, board: Vec<Token>) -> Self {
+        let size = regions.size();
+        assert_eq!(
+            board.len(),
+            size.cells(),
+            "Board does not have the {} cells of a {size} game",
+            size.cells()
+        );
+
+        let mut game = Self {
+            size,
+            regions,
+            cages: None,
+            variants: Vec::new(),
+            constraints: Vec::new(),
+            givens: board.iter().map(|token| *token != Token::None).collect(),
+            board,
+            marks: Vec::new(),
+            rows: vec![0; size.side()],
+            columns: vec![0; size.side()],
+            sectors: vec![0; size.side()],
+        };
+
+        for i in 0..size.side() {
+            game.rows[i] = game.mask_of(RowIndexer::new(size, i));
+            game.columns[i] = game.mask_of(ColumnIndexer::new(size, i));
+            game.sectors[i] = game.mask_of(game.regions.cells(i).iter().copied());
+        }
+        game
     }
 
-    pub fn new_solved() -> Self {
-        ops::generate_solved()
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    #[must_use]
+    pub fn with_constraint(mut self, constraint: impl Constraint + 'static) -> Self {
+        self.constraints.push(Arc::new(constraint));
+        self
+    }
+
+    // The cages are kept apart from the other constraints as well, so that they can be written out
+    #[must_use]
+    pub fn with_cages(mut self, cages: Cages) -> Self {
+        assert!(
+            cages.size() == self.size,
+            "Cages do not fit a {} game",
+            self.size
+        );
+        self.cages = Some(cages.clone());
+        self.with_constraint(cages)
+    }
+
+    #[inline]
+    pub fn cages(&self) -> Option<&Cages> {
+        self.cages.as_ref()
+    }
+
+    // Variants are kept apart as well, and given once
+    #[must_use]
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        assert!(
+            variant.fits(self.size),
+            "{variant} does not fit a {} game",
+            self.size
+        );
+        if self.variants.contains(&variant) {
+            return self;
+        }
+        self.variants.push(variant);
+        self.with_constraint(variant)
+    }
+
+    #[inline]
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    #[inline]
+    pub fn constraints(&self) -> &[Arc<dyn Constraint>] {
+        &self.constraints
     }
 
     #[inline]
     pub fn get(&self, cell: Cell) -> Token {
-        self.board[cell.index()]
+        self.board[self.size.index(cell)]
     }
 
+    // Filled cells of a new game are its givens, and the tokens placed later are not. Solving
+    // keeps them, and this makes the filled cells the givens of a puzzle made some other way
     #[must_use]
-    pub fn set(&mut self, cell: Cell, token: Token) -> bool {
+    pub fn with_givens(mut self) -> Self {
+        self.givens = self
+            .board
+            .iter()
+            .map(|token| *token != Token::None)
+            .collect();
+        self
+    }
+
+    // Givens that were taken out while making the puzzle are not givens anymore
+    #[inline]
+    pub fn is_given(&self, cell: Cell) -> bool {
+        let index = self.size.index(cell);
+        self.givens[index] && self.board[index] != Token::None
+    }
+
+    // Returns whether the token keeps the board consistent, or an error if the cell is a given,
+    // which is left alone. Placing a token also takes it out of the pencil marks of the row,
+    // column and region
+    pub fn set(&mut self, cell: Cell, token: Token) -> Result<bool, Error> {
+        if self.is_given(cell) {
+            return error!("{cell} is a given");
+        }
+
         self.set_internal(cell, token);
-        ops::consistent(&self, cell, token)
+        if token != Token::None && !self.marks.is_empty() {
+            let region = self.regions.region(cell);
+            let peers = RowIndexer::new(self.size, cell.row())
+                .chain(ColumnIndexer::new(self.size, cell.column()))
+                .chain(self.regions.cells(region).iter().copied())
+                .filter(|peer| *peer != cell)
+                .collect::<Vec<_>>();
+            for peer in peers {
+                self.marks[self.size.index(peer)].remove(token);
+            }
+        }
+        Ok(ops::consistent(self, cell, token))
     }
 
     fn set_internal(&mut self, cell: Cell, token: Token) {
-        self.board[cell.index()] = token;
-        self.columns[cell.column()][cell.row()] = token;
-        self.sectors[cell.sector()][cell.sector_index()] = token;
+        let previous = std::mem::replace(&mut self.board[self.size.index(cell)], token);
+        let sector = self.regions.region(cell);
+
+        if previous == Token::None {
+            let mask = token.mask();
+            self.rows[cell.row()] |= mask;
+            self.columns[cell.column()] |= mask;
+            self.sectors[sector] |= mask;
+        } else {
+            // The previous token might still be present elsewhere in the units if the board is
+            // inconsistent, so the masks need to be rebuilt instead of just cleared
+            self.rows[cell.row()] = self.mask_of(RowIndexer::new(self.size, cell.row()));
+            self.columns[cell.column()] =
+                self.mask_of(ColumnIndexer::new(self.size, cell.column()));
+            self.sectors[sector] = self.mask_of(self.regions.cells(sector).iter().copied());
+        }
     }
 
-    pub fn solve(&self) -> Option<Self> {
-        ops::solve(&self, false, true)
+    fn mask_of(&self, unit: impl Iterator<Item = Cell>) -> u32 {
+        unit.fold(0, |mask, cell| mask | self.get(cell).mask())
     }
 
-    pub fn shuffle(self, seed: u64) -> Self {
-        use rand::{Rng, SeedableRng};
+    #[inline]
+    pub fn candidates(&self, cell: Cell) -> Candidates {
+        if self.get(cell) == Token::None {
+            let candidates = self.unit_candidates(cell);
+            self.constraints
+                .iter()
+                .fold(candidates, |candidates, constraint| {
+                    constraint.eliminate(self, cell, candidates)
+                })
+        } else {
+            Candidates::empty()
+        }
+    }
 
-        let mut board = self.board;
-        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    // Candidates left by the rows, columns and regions alone
+    #[inline]
+    fn unit_candidates(&self, cell: Cell) -> Candidates {
+        Candidates::from_mask(
+            self.size.mask()
+                & !(self.rows[cell.row()]
+                    | self.columns[cell.column()]
+                    | self.sectors[self.regions.region(cell)]),
+        )
+    }
+
+    // Filled cells keep their marks, which show again once the token is erased
+    #[inline]
+    pub fn marks(&self, cell: Cell) -> Candidates {
+        self.marks
+            .get(self.size.index(cell))
+            .copied()
+            .unwrap_or_else(Candidates::empty)
+    }
 
-        for _ in 0..128 {
-            match rng.gen::<u8>() % 8 {
-                0 => transform::shift(&mut board, rng.gen::<u8>()),
-                1 => transform::rotate(&mut board),
-                2 => transform::mirror_columns(&mut board),
-                3 => transform::mirror_rows(&mut board),
-                4 => transform::swap_columns(&mut board, rng.gen::<usize>(), rng.gen::<usize>()),
-                5 => transform::swap_rows(&mut board, rng.gen::<usize>(), rng.gen::<usize>()),
-                6 => transform::swap_column_sector(&mut board, rng.gen::<usize>()),
-                7 => transform::swap_row_sector(&mut board, rng.gen::<usize>()),
-                _ => unreachable!(),
+    // Returns whether the token is marked afterwards
+    pub fn toggle_mark(&mut self, cell: Cell, token: Token) -> bool {
+        if self.marks.is_empty() {
+            self.marks = vec![Candidates::empty(); self.size.cells()];
+        }
+        let marks = &mut self.marks[self.size.index(cell)];
+        if marks.contains(token) {
+            marks.remove(token);
+            false
+        } else {
+            marks.insert(token);
+            true
+        }
+    }
+
+    // Marks every candidate of the empty cells, replacing the marks there were. Filled cells keep
+    // theirs
+    pub fn fill_marks(&mut self) {
+        if self.marks.is_empty() {
+            self.marks = vec![Candidates::empty(); self.size.cells()];
+        }
+        for cell in BoardIndexer::new(self.size) {
+            if self.get(cell) == Token::None {
+                self.marks[self.size.index(cell)] = self.candidates(cell);
             }
         }
+    }
 
-        Game::from(board)
+    pub fn clear_marks(&mut self) {
+        self.marks = Vec::new();
     }
 
-    pub fn prune_per_gaps(&self, max_difficulty: Difficulty) -> [Option<Game>; 3] {
-        ops::prune_per_gaps(self, max_difficulty)
+    pub fn clues(&self) -> usize {
+        self.board
+            .iter()
+            .filter(|token| **token != Token::None)
+            .count()
     }
 
-    pub fn prune_per_time(&self, max_difficulty: Difficulty) -> [Option<Game>; 3] {
-        ops::prune_per_duration(self, max_difficulty)
+    pub fn is_solved(&self) -> bool {
+        ops::solved(self)
+    }
+
+    pub fn is_minimal(&self) -> bool {
+        ops::minimal(self)
     }
-}
 
-impl std::convert::From<Board> for Game {
-    fn from(board: Board) -> Self {
-        let mut columns = [[Token::None; 9]; 9];
-        let mut sectors = [[Token::None; 9]; 9];
+    pub fn solve(&self, rng: &mut impl rand::Rng) -> Option<Self> {
+        ops::solve(self, rng)
+    }
 
-        for i in 0..9 {
-            for (index, cell) in ColumnIndexer::new(i).enumerate() {
-                columns[i][index] = board[cell.index()];
+    pub fn solve_logically(&self) -> Trace {
+        logic::solve(self)
+    }
+
+    // Compares the board a player made out of this puzzle with its solution
+    pub fn check(&self, board: &Self) -> Result<Check, Error> {
+        check::check(self, board)
+    }
+
+    // The placed tokens that break a rule
+    pub fn clashes(&self) -> Vec<Cell> {
+        ops::clashes(self)
+    }
+
+    // The easiest deduction left, if the known techniques find one. Boards that break a rule have
+    // none, since the deductions would rest on the clash
+    pub fn next_hint(&self) -> Option<Hint> {
+        hint::next(self)
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        ops::count_solutions(self, limit)
+    }
+
+    pub fn solutions(&self, limit: usize) -> Vec<Self> {
+        ops::solutions(self, limit)
+    }
+
+    // Draws one of the symmetries of the board, all as likely, and returns it along with the
+    // shuffled game so that other boards can follow. The whole group is drawn from, unless a
+    // symmetry of the clue pattern, jigsaw regions or variants limit it to relabelling the tokens
+    // along with the mirrors and the transpose, since moving single lines would break the pattern,
+    // split the regions apart or move cells off the diagonals and windows. Transposing would turn
+    // rectangular sectors on their side, so it is only used with square ones. Other constraints can
+    // depend on both the positions and the values of the tokens, so their boards are left alone
+    pub fn shuffle(&self, seed: u64, symmetry: Symmetry) -> (Self, Transformation) {
+        use rand::{Rng, SeedableRng};
+
+        let size = self.size;
+        if self.constraints.len() > self.variants.len() {
+            return (self.clone(), Transformation::identity(size));
+        }
+
+        let jigsaw = !self.regions.is_standard();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let transformation = if symmetry == Symmetry::None && !jigsaw && self.variants.is_empty() {
+            Transformation::random(size, &mut rng)
+        } else {
+            let mut transformation = Transformation::random_tokens(size, &mut rng);
+            let mut dihedral = Vec::new();
+            if symmetry.survives_transpose() && (size.is_square() || jigsaw) {
+                dihedral.push(Transformation::transpose(size));
+            }
+            if symmetry.survives_mirror() {
+                dihedral.push(Transformation::mirror_rows(size));
+                dihedral.push(Transformation::mirror_columns(size));
             }
-            for (index, cell) in SectorIndexer::new(i).enumerate() {
-                sectors[i][index] = board[cell.index()];
+            for other in &dihedral {
+                if rng.gen() {
+                    transformation = transformation.then(other);
+                }
             }
+            transformation
+        };
+
+        let game = self
+            .transform(&transformation)
+            .expect("Shuffles keep to the symmetries of the game");
+        (game, transformation)
+    }
+
+    // Moves the cells and relabels the tokens, along with the givens and the pencil marks. Fails
+    // unless the game survives the transformation: the cages and other constraints tie the tokens
+    // down, jigsaw regions must stay connected, and variants only survive the mirrors and the
+    // transpose
+    pub fn transform(&self, transformation: &Transformation) -> Result<Self, Error> {
+        let size = self.size;
+        if transformation.size() != size {
+            return error!(
+                "the transformation is for {:?} boards",
+                transformation.size()
+            );
         }
-        Self {
-            board,
-            columns,
-            sectors,
+        if self.constraints.len() > self.variants.len() {
+            return error!("the constraints of the game do not survive transformations");
+        }
+        if !self.variants.is_empty() && !transformation.is_dihedral() {
+            return error!("variants only survive the mirrors and the transpose");
+        }
+        if transformation.is_transposed() && self.regions.is_standard() && !size.is_square() {
+            return error!("transposing would turn the sectors of the game on their side");
+        }
+
+        let mut labels = self.regions.labels();
+        transformation.permute(&mut labels);
+        let Some(regions) = Regions::new(size, &labels) else {
+            return error!("the regions of the game would not stay connected");
+        };
+
+        let mut board = self.board.clone();
+        transformation.apply(&mut board);
+        let mut game = self
+            .variants
+            .iter()
+            .fold(Game::with_regions(regions, board), |game, variant| {
+                game.with_variant(*variant)
+            });
+
+        let mut givens = self.givens.to_vec();
+        transformation.permute(&mut givens);
+        game.givens = givens.into();
+        game.marks = self
+            .marks
+            .iter()
+            .map(|marks| {
+                let mut moved = Candidates::empty();
+                for token in marks.iter() {
+                    moved.insert(transformation.token(token));
+                }
+                moved
+            })
+            .collect();
+        if !game.marks.is_empty() {
+            transformation.permute(&mut game.marks);
         }
+        Ok(game)
+    }
+
+    // Same board for every shuffle of a puzzle, to find the ones that are the same underneath
+    pub fn canonical(&self) -> Self {
+        canonical::canonical(self)
+    }
+
+    // Hash of the canonical board, which stays the same across runs and builds
+    pub fn fingerprint(&self) -> u64 {
+        canonical::fingerprint(self)
+    }
+
+    // Killer puzzle with this solved board as its only solution, which has cages instead of clues
+    pub fn killer(&self, rng: &mut impl rand::Rng) -> Self {
+        ops::generate_killer(self, rng)
+    }
+
+    pub fn rate(&self) -> Difficulty {
+        self.solve_logically().difficulty()
+    }
+
+    pub fn prune(
+        &self,
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+        rng: &mut impl rand::Rng,
+    ) -> Option<Self> {
+        ops::prune(self, difficulty, symmetry, rng)
     }
 }
 
 impl std::convert::From<[u8; 81]> for Game {
     fn from(board: [u8; 81]) -> Self {
-        Self::from(tokenize(board))
+        Self::with_tokens(Size::STANDARD, board.iter().map(Token::from).collect())
+    }
+}
+
+// Junctions by the weight of the line going up, down, left and right, as in base 3
+// Allowed because it is more readable
+#[allow(clippy::non_ascii_literal)]
+const JUNCTIONS: [char; 81] = [
+    ' ', '╶', '╺', '╴', '─', '╼', '╸', '╾', '━', '╷', '┌', '┍', '┐', '┬', '┮', '┑', '┭', '┯', '╻',
+    '┎', '┏', '┒', '┰', '┲', '┓', '┱', '┳', '╵', '└', '┕', '┘', '┴', '┶', '┙', '┵', '┷', '│', '├',
+    '┝', '┤', '┼', '┾', '┥', '┽', '┿', '╽', '┟', '┢', '┧', '╁', '╆', '┪', '╅', '╈', '╹', '┖', '┗',
+    '┚', '┸', '┺', '┛', '┹', '┻', '╿', '┞', '┡', '┦', '╀', '╄', '┩', '╃', '╇', '┃', '┠', '┣', '┨',
+    '╂', '╊', '┫', '╉', '╋',
+];
+
+impl Game {
+    fn fmt_token(&self, fmt: &mut std::fmt::Formatter<'_>, cell: Cell) -> std::fmt::Result {
+        let token = self.get(cell);
+        if fmt.alternate() && self.is_given(cell) {
+            write!(fmt, "\x1b[1m{token}\x1b[22m")
+        } else {
+            write!(fmt, "{token}")
+        }
+    }
+
+    // Edges between regions are drawn thick and edges between cages thin, so each row is followed by
+    // a line of borders. Cages also get a line above each row for their sums
+    // Allowed because it is more readable
+    #[allow(clippy::non_ascii_literal)]
+    fn fmt_outlined(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = self.size.side();
+        let width = self.cages.as_ref().map_or(1, Cages::width);
+        // Region and cage at the given row and column, if it is on the board. Rows and columns are
+        // shifted by one, so that the outside above and to the left can be asked for too
+        let unit = |row: usize, column: usize| {
+            if row == 0 || column == 0 || row > side || column > side {
+                None
+            } else {
+                let cell = Cell::new(row - 1, column - 1);
+                let cage = self.cages.as_ref().and_then(|cages| cages.index(cell));
+                Some((self.regions.region(cell), cage))
+            }
+        };
+        let weight = |first: Option<(usize, Option<usize>)>,
+                      second: Option<(usize, Option<usize>)>| {
+            if first.map(|(region, _)| region) == second.map(|(region, _)| region) {
+                usize::from(first != second)
+            } else {
+                2
+            }
+        };
+
+        for row in 0..=side {
+            for column in 0..=side {
+                let up = weight(unit(row, column), unit(row, column + 1));
+                let down = weight(unit(row + 1, column), unit(row + 1, column + 1));
+                let left = weight(unit(row, column), unit(row + 1, column));
+                let right = weight(unit(row, column + 1), unit(row + 1, column + 1));
+                write!(fmt, "{}", JUNCTIONS[up * 27 + down * 9 + left * 3 + right])?;
+                if column < side {
+                    let edge = [' ', '─', '━'][right];
+                    write!(fmt, "{}", edge.to_string().repeat(width))?;
+                }
+            }
+            writeln!(fmt)?;
+
+            if row == side {
+                break;
+            }
+            if let Some(cages) = &self.cages {
+                for column in 0..=side {
+                    let edge = weight(unit(row + 1, column), unit(row + 1, column + 1));
+                    write!(fmt, "{}", [' ', '│', '┃'][edge])?;
+                    if column < side {
+                        let cell = Cell::new(row, column);
+                        match cages.cage(cell) {
+                            Some(cage) if cage.cells()[0] == cell => {
+                                write!(fmt, "{:<width$}", cage.sum())?;
+                            }
+                            _ => write!(fmt, "{:width$}", "")?,
+                        }
+                    }
+                }
+                writeln!(fmt)?;
+            }
+            for column in 0..=side {
+                let edge = weight(unit(row + 1, column), unit(row + 1, column + 1));
+                write!(fmt, "{}", [' ', '│', '┃'][edge])?;
+                if column < side {
+                    write!(fmt, "{:>pad$}", "", pad = width - 1)?;
+                    self.fmt_token(fmt, Cell::new(row, column))?;
+                }
+            }
+            writeln!(fmt)?;
+        }
+        Ok(())
     }
 }
 
+// The alternate form draws the givens in bold, for terminals
 impl std::fmt::Display for Game {
     // Allowed because it is more readable
     #[allow(clippy::non_ascii_literal)]
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(fmt, "┏━━━━━┯━━━━━┯━━━━━┓")?;
-        for row in 0..9 {
+        if !self.regions.is_standard() || self.cages.is_some() {
+            return self.fmt_outlined(fmt);
+        }
+
+        let side = self.size.side();
+        let sector_rows = self.size.sector_rows();
+        let sector_columns = self.size.sector_columns();
+
+        let border = |fmt: &mut std::fmt::Formatter<'_>, line: [&str; 4]| {
+            write!(fmt, "{}", line[0])?;
+            for sector in 0..side / sector_columns {
+                if sector > 0 {
+                    write!(fmt, "{}", line[2])?;
+                }
+                write!(fmt, "{}", line[1].repeat(sector_columns * 2 - 1))?;
+            }
+            writeln!(fmt, "{}", line[3])
+        };
+
+        border(fmt, ["┏", "━", "┯", "┓"])?;
+        for row in 0..side {
             write!(fmt, "┃")?;
-            for col in 0..8 {
-                let cell = Cell::new(row, col);
-                if col % 3 == 2 {
-                    write!(fmt, "{}│", self.get(cell))?;
+            for column in 0..side {
+                self.fmt_token(fmt, Cell::new(row, column))?;
+                if column == side - 1 {
+                    writeln!(fmt, "┃")?;
+                } else if column % sector_columns == sector_columns - 1 {
+                    write!(fmt, "│")?;
                 } else {
-                    write!(fmt, "{} ", self.get(cell))?;
+                    write!(fmt, " ")?;
                 }
             }
-            writeln!(fmt, "{}┃", self.get(Cell::new(row, 8)))?;
 
-            if row < 8 && row % 3 == 2 {
-                writeln!(fmt, "┠─────┼─────┼─────┨")?;
+            if row < side - 1 && row % sector_rows == sector_rows - 1 {
+                border(fmt, ["┠", "─", "┼", "┨"])?;
             }
         }
-        writeln!(fmt, "┗━━━━━┷━━━━━┷━━━━━┛")
+        border(fmt, ["┗", "━", "┷", "┛"])
     }
 }
 
+// Jigsaw regions, sector shapes other than the usual one for the board, cages and variants are
+// written as prefixes
 impl std::fmt::Debug for Game {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in 0..80 {
-            write!(fmt, "{:?},", self.board[i])?;
+        if !self.regions.is_standard() {
+            write!(fmt, "{}:", self.regions)?;
+        } else if Size::from_cells(self.size.cells()) != Some(self.size) {
+            write!(
+                fmt,
+                "{}x{}:",
+                self.size.sector_rows(),
+                self.size.sector_columns()
+            )?;
+        }
+        if let Some(cages) = &self.cages {
+            write!(fmt, "{cages}:")?;
+        }
+        if let Some((first, rest)) = self.variants.split_first() {
+            write!(fmt, "{first}")?;
+            for variant in rest {
+                write!(fmt, ",{variant}")?;
+            }
+            write!(fmt, ":")?;
+        }
+
+        let (last, rest) = self.board.split_last().expect("Boards are never empty");
+        for token in rest {
+            write!(fmt, "{token:?},")?;
         }
-        write!(fmt, "{:?}", self.board[80])
+        write!(fmt, "{last:?}")
     }
 }
 
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Difficulty {
+    Beginner,
     Easy,
     Medium,
     Hard,
+    Expert,
+    Diabolical,
 }
 
 impl Difficulty {
-    fn to_gaps(self) -> usize {
+    pub fn name(self) -> &'static str {
         match self {
-            Difficulty::Easy => 40,
-            Difficulty::Medium => 50,
-            Difficulty::Hard => 60,
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+            Difficulty::Diabolical => "Diabolical",
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Symmetry {
+    None,
+    Rot180,
+    Rot90,
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+    Dihedral,
+}
+
+impl Symmetry {
+    // The cells that must be kept or removed together with the given cell
+    pub fn orbit(self, size: Size, cell: Cell) -> Vec<Cell> {
+        let (row, column) = (cell.row(), cell.column());
+        let last = size.side() - 1;
+        let images = [
+            (row, column),
+            (last - row, last - column),
+            (column, last - row),
+            (last - column, row),
+            (last - row, column),
+            (row, last - column),
+            (column, row),
+            (last - column, last - row),
+        ];
+
+        let used: &[usize] = match self {
+            Symmetry::None => &[0],
+            Symmetry::Rot180 => &[0, 1],
+            Symmetry::Rot90 => &[0, 1, 2, 3],
+            Symmetry::Horizontal => &[0, 4],
+            Symmetry::Vertical => &[0, 5],
+            Symmetry::Diagonal => &[0, 6],
+            Symmetry::AntiDiagonal => &[0, 7],
+            Symmetry::Dihedral => &[0, 1, 2, 3, 4, 5, 6, 7],
+        };
+
+        let mut orbit = Vec::with_capacity(used.len());
+        for (row, column) in used.iter().map(|image| images[*image]) {
+            let cell = Cell::new(row, column);
+            if !orbit.contains(&cell) {
+                orbit.push(cell);
+            }
         }
+        orbit
     }
 
-    fn to_duration(self) -> std::time::Duration {
-        std::time::Duration::from_secs(match self {
-            Difficulty::Easy => 1,
-            Difficulty::Medium => 30,
-            Difficulty::Hard => 60,
-        })
+    // Transposing swaps the horizontal and vertical mirrors
+    fn survives_transpose(self) -> bool {
+        !matches!(self, Symmetry::Horizontal | Symmetry::Vertical)
+    }
+
+    // Mirroring swaps the diagonals
+    fn survives_mirror(self) -> bool {
+        !matches!(self, Symmetry::Diagonal | Symmetry::AntiDiagonal)
     }
 }
 
@@ -174,10 +747,26 @@ pub enum Token {
     Seven = 7,
     Eight = 8,
     Nine = 9,
+    Ten = 10,
+    Eleven = 11,
+    Twelve = 12,
+    Thirteen = 13,
+    Fourteen = 14,
+    Fifteen = 15,
+    Sixteen = 16,
+    Seventeen = 17,
+    Eighteen = 18,
+    Nineteen = 19,
+    Twenty = 20,
+    TwentyOne = 21,
+    TwentyTwo = 22,
+    TwentyThree = 23,
+    TwentyFour = 24,
+    TwentyFive = 25,
 }
 
 impl Token {
-    const TOKENS: [Self; 10] = [
+    const TOKENS: [Self; 26] = [
         Token::None,
         Token::One,
         Token::Two,
@@ -188,11 +777,42 @@ impl Token {
         Token::Seven,
         Token::Eight,
         Token::Nine,
+        Token::Ten,
+        Token::Eleven,
+        Token::Twelve,
+        Token::Thirteen,
+        Token::Fourteen,
+        Token::Fifteen,
+        Token::Sixteen,
+        Token::Seventeen,
+        Token::Eighteen,
+        Token::Nineteen,
+        Token::Twenty,
+        Token::TwentyOne,
+        Token::TwentyTwo,
+        Token::TwentyThree,
+        Token::TwentyFour,
+        Token::TwentyFive,
     ];
 
+    // Digits first and then letters, so that every token fits in a single character
+    pub fn from_char(character: char) -> Option<Self> {
+        match character {
+            '.' => Some(Token::None),
+            _ => character
+                .to_digit(36)
+                .and_then(|value| Self::TOKENS.get(value as usize))
+                .copied(),
+        }
+    }
+
     #[inline]
-    pub fn list() -> &'static [Self] {
-        &Self::TOKENS[1..10]
+    fn mask(self) -> u32 {
+        if self == Token::None {
+            0
+        } else {
+            1 << (self as u8 - 1)
+        }
     }
 }
 
@@ -210,10 +830,11 @@ impl std::convert::From<&u8> for Token {
 
 impl std::fmt::Display for Token {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self == &Token::None {
-            write!(fmt, " ")
-        } else {
-            write!(fmt, "{}", *self as u8)
+        match std::char::from_digit(u32::from(*self as u8), 36) {
+            Some(character) if self != &Token::None => {
+                write!(fmt, "{}", character.to_ascii_uppercase())
+            }
+            _ => write!(fmt, " "),
         }
     }
 }
@@ -224,118 +845,471 @@ impl std::fmt::Debug for Token {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct Cell(usize);
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Candidates(u32);
 
-impl Cell {
+impl Candidates {
     #[inline]
-    pub fn new(row: u8, column: u8) -> Self {
-        assert!(
-            row < 9 && column < 9,
-            "Cell aout of bounds (row: {}, column: {})",
-            row,
-            column
-        );
-        Self(usize::from(row * 9 + column))
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    #[inline]
+    pub fn all(size: Size) -> Self {
+        Self(size.mask())
+    }
+
+    #[inline]
+    fn from_mask(mask: u32) -> Self {
+        Self(mask)
     }
 
     #[inline]
-    pub fn sector(self) -> usize {
-        let row = self.0 / 9;
-        let col = self.0 % 9;
-        (row / 3) * 3 + col / 3
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
     }
 
     #[inline]
-    fn sector_index(self) -> usize {
-        let row = self.0 / 9;
-        let col = self.0 % 9;
-        (row % 3) * 3 + (col % 3)
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
     }
 
     #[inline]
-    pub(super) fn index(self) -> usize {
-        self.0
+    pub fn contains(self, token: Token) -> bool {
+        self.0 & token.mask() != 0
     }
 
     #[inline]
-    fn row(self) -> usize {
-        self.0 / 9
+    pub fn insert(&mut self, token: Token) {
+        self.0 |= token.mask();
     }
 
     #[inline]
-    fn column(self) -> usize {
-        self.0 % 9
+    pub fn remove(&mut self, token: Token) {
+        self.0 &= !token.mask();
+    }
+
+    #[inline]
+    pub fn single(self) -> Option<Token> {
+        if self.len() == 1 {
+            Some(Token::TOKENS[self.0.trailing_zeros() as usize + 1])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Token> {
+        Token::TOKENS[1..]
+            .iter()
+            .copied()
+            .filter(move |token| self.contains(*token))
     }
 }
 
-impl std::convert::From<usize> for Cell {
-    fn from(index: usize) -> Self {
-        assert!(index < 81, "Index out of bounds: {}", index);
-        Self(index)
+impl std::ops::BitOr for Candidates {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
     }
 }
 
-impl std::convert::From<&u8> for Cell {
-    fn from(index: &u8) -> Self {
-        Self::from(usize::from(*index))
+impl std::ops::BitAnd for Candidates {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
     }
 }
 
-impl std::convert::From<u8> for Cell {
-    fn from(index: u8) -> Self {
-        Self::from(usize::from(index))
+// Only meaningful when combined with candidates of the same size, since it sets every bit
+impl std::ops::Not for Candidates {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0)
     }
 }
 
-impl std::fmt::Debug for Cell {
+impl std::fmt::Debug for Candidates {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            fmt,
-            "[raw: {}, row: {}, column: {}, sector: {}, sector_index: {}]",
-            self.0,
-            self.row(),
-            self.column(),
-            self.sector(),
-            self.sector_index()
-        )
+        fmt.debug_set().entries(self.iter()).finish()
     }
 }
 
-fn tokenize(board: [u8; 81]) -> Board {
-    unsafe { std::mem::transmute::<[u8; 81], [Token; 81]>(board) }
+// Shape of the sectors. A board has as many rows, columns and tokens as a sector has cells
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Size {
+    rows: usize,
+    columns: usize,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{ops, transform, Board, Game, Token};
-    use crate::index::{BoardIndexer, ColumnIndexer, RowIndexer, SectorIndexer};
+impl Size {
+    pub const STANDARD: Self = Self {
+        rows: 3,
+        columns: 3,
+    };
 
-    fn assert_no_empty(board: &Board) {
-        for cell in BoardIndexer::new() {
-            assert_ne!(board[cell.index()], Token::None);
+    pub fn new(rows: usize, columns: usize) -> Option<Self> {
+        if rows < 2 || columns < 2 || rows * columns > Token::TOKENS.len() - 1 {
+            None
+        } else {
+            Some(Self { rows, columns })
         }
     }
 
+    // Sectors as square as possible, and wider than tall when they cannot be square
+    pub fn from_side(side: usize) -> Option<Self> {
+        let rows = (2..=side)
+            .take_while(|rows| rows * rows <= side)
+            .filter(|rows| side.is_multiple_of(*rows))
+            .last()?;
+        Self::new(rows, side / rows)
+    }
+
+    pub fn from_cells(cells: usize) -> Option<Self> {
+        let side = (2..=Token::TOKENS.len()).find(|side| side * side >= cells)?;
+        if side * side == cells {
+            Self::from_side(side)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn sector_rows(self) -> usize {
+        self.rows
+    }
+
+    #[inline]
+    pub fn sector_columns(self) -> usize {
+        self.columns
+    }
+
+    #[inline]
+    pub fn is_square(self) -> bool {
+        self.rows == self.columns
+    }
+
+    #[inline]
+    pub fn side(self) -> usize {
+        self.rows * self.columns
+    }
+
+    #[inline]
+    pub fn cells(self) -> usize {
+        self.side() * self.side()
+    }
+
+    #[inline]
+    pub fn tokens(self) -> &'static [Token] {
+        &Token::TOKENS[1..=self.side()]
+    }
+
+    #[inline]
+    fn mask(self) -> u32 {
+        (1 << self.side()) - 1
+    }
+
+    #[inline]
+    pub fn contains(self, cell: Cell) -> bool {
+        cell.row() < self.side() && cell.column() < self.side()
+    }
+
+    #[inline]
+    pub fn index(self, cell: Cell) -> usize {
+        cell.row() * self.side() + cell.column()
+    }
+
+    #[inline]
+    pub fn cell(self, index: usize) -> Cell {
+        assert!(index < self.cells(), "Index out of bounds: {}", index);
+        Cell::new(index / self.side(), index % self.side())
+    }
+
+    #[inline]
+    pub fn sector(self, cell: Cell) -> usize {
+        (cell.row() / self.rows) * self.rows + cell.column() / self.columns
+    }
+
+    #[inline]
+    fn sector_index(self, cell: Cell) -> usize {
+        (cell.row() % self.rows) * self.columns + cell.column() % self.columns
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{side}x{side}", side = self.side())
+    }
+}
+
+impl std::fmt::Debug for Size {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{self} ({}x{} sectors)", self.rows, self.columns)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Cell {
+    row: usize,
+    column: usize,
+}
+
+impl Cell {
+    #[inline]
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+
+    #[inline]
+    pub fn row(self) -> usize {
+        self.row
+    }
+
+    #[inline]
+    pub fn column(self) -> usize {
+        self.column
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "r{}c{}", self.row + 1, self.column + 1)
+    }
+}
+
+impl std::fmt::Debug for Cell {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "[row: {}, column: {}]", self.row, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ops, transform, Cage, Cages, Candidates, Cell, Game, Regions, Size, Symmetry, Token,
+        Transformation, Variant,
+    };
+    use crate::index::{BoardIndexer, RowIndexer};
+
+    fn assert_no_empty(board: &[Token]) {
+        assert!(board.iter().all(|token| *token != Token::None));
+    }
+
     #[test]
     fn game_from_array() {
-        #[rustfmt::skip]
         let reference = ops::consistent_board();
         let game = Game::from(reference);
 
         for i in 0..9 {
-            for cell in RowIndexer::new(i) {
-                assert_eq!(game.get(cell), reference[cell.index()]);
+            for cell in RowIndexer::new(Size::STANDARD, i) {
+                assert_eq!(
+                    game.get(cell),
+                    Token::from(reference[Size::STANDARD.index(cell)])
+                );
             }
 
-            for (index, cell) in ColumnIndexer::new(i).enumerate() {
-                assert_eq!(game.columns[i][index], reference[cell.index()]);
-            }
+            assert_eq!(game.rows[i], Size::STANDARD.mask());
+            assert_eq!(game.columns[i], Size::STANDARD.mask());
+            assert_eq!(game.sectors[i], Size::STANDARD.mask());
+        }
+    }
 
-            for (index, cell) in SectorIndexer::new(i).enumerate() {
-                assert_eq!(game.sectors[i][index], reference[cell.index()]);
-            }
+    #[test]
+    fn masks() {
+        let mut game = Game::new_empty(Size::STANDARD);
+        let cell = Cell::new(4, 5);
+
+        game.set_internal(cell, Token::Three);
+        assert_eq!(game.rows[4], 0b100);
+        assert_eq!(game.columns[5], 0b100);
+        assert_eq!(game.sectors[4], 0b100);
+
+        game.set_internal(Cell::new(4, 0), Token::Three);
+        game.set_internal(cell, Token::Seven);
+        assert_eq!(game.rows[4], 0b100_0100);
+        assert_eq!(game.columns[5], 0b100_0000);
+        assert_eq!(game.sectors[4], 0b100_0000);
+
+        game.set_internal(cell, Token::None);
+        assert_eq!(game.rows[4], 0b100);
+        assert_eq!(game.columns[5], 0);
+        assert_eq!(game.sectors[4], 0);
+    }
+
+    #[test]
+    fn clues() {
+        let mut game = Game::from(ops::consistent_board());
+        assert_eq!(game.clues(), 81);
+
+        for cell in RowIndexer::new(Size::STANDARD, 3) {
+            game.set_internal(cell, Token::None);
+        }
+        assert_eq!(game.clues(), 72);
+        assert_eq!(Game::new_empty(Size::STANDARD).clues(), 0);
+    }
+
+    #[test]
+    fn candidates() {
+        #[rustfmt::skip]
+        let game = Game::from([
+            0, 0, 0, 0, 0, 0, 0, 0, 9,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            2, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 5, 3,
+        ]);
+
+        let candidates = game.candidates(Cell::new(8, 0));
+        assert_eq!(candidates.len(), 6);
+        for token in [Token::Two, Token::Three, Token::Five] {
+            assert!(!candidates.contains(token));
+        }
+        assert_eq!(
+            candidates.iter().collect::<Vec<_>>(),
+            vec![
+                Token::One,
+                Token::Four,
+                Token::Six,
+                Token::Seven,
+                Token::Eight,
+                Token::Nine,
+            ]
+        );
+
+        assert!(game.candidates(Cell::new(8, 8)).is_empty());
+        assert_eq!(
+            game.candidates(Cell::new(4, 4)),
+            Candidates::all(Size::STANDARD)
+        );
+
+        let game = Game::new_empty(Size::new(2, 3).unwrap());
+        assert_eq!(game.candidates(Cell::new(5, 5)).len(), 6);
+    }
+
+    #[test]
+    fn givens() {
+        let mut game = Game::from(ops::hard_board());
+        let given = Cell::new(0, 0);
+        let open = Cell::new(0, 1);
+        assert!(game.is_given(given));
+        assert!(!game.is_given(open));
+
+        let token = game.get(given);
+        assert!(game.set(given, Token::None).is_err());
+        assert!(game.set(given, Token::Two).is_err());
+        assert_eq!(game.get(given), token);
+
+        assert!(game.set(open, Token::Two).is_ok());
+        assert!(!game.is_given(open));
+        assert!(game.set(open, Token::None).unwrap());
+
+        let solved = game.solve(&mut rand::thread_rng()).unwrap();
+        assert!(solved.is_given(given));
+        assert!(!solved.is_given(open));
+        assert!(solved.with_givens().is_given(open));
+
+        // Only the alternate form sets the givens apart
+        assert!(!game.to_string().contains('\x1b'));
+        let alternate = format!("{game:#}");
+        assert_eq!(alternate.matches("\x1b[1m").count(), game.clues());
+        assert!(alternate.starts_with(&format!("┏━━━━━┯━━━━━┯━━━━━┓\n┃\x1b[1m{token}\x1b[22m ")));
+    }
+
+    #[test]
+    fn marks() {
+        let mut game = Game::from(ops::hard_board());
+        let cell = Cell::new(0, 1);
+        assert!(game.marks(cell).is_empty());
+        assert!(game.toggle_mark(cell, Token::Four));
+        assert!(game.toggle_mark(cell, Token::Six));
+        assert!(!game.toggle_mark(cell, Token::Four));
+        assert_eq!(
+            game.marks(cell).iter().collect::<Vec<_>>(),
+            vec![Token::Six]
+        );
+
+        game.fill_marks();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert!(game.marks(cell) == game.candidates(cell));
+        }
+
+        // Only the peers lose the token
+        let marked = |game: &Game, token| {
+            BoardIndexer::new(Size::STANDARD)
+                .filter(|cell| game.marks(*cell).contains(token))
+                .collect::<Vec<_>>()
+        };
+        let token = game.candidates(cell).iter().next().unwrap();
+        let before = marked(&game, token);
+        assert!(game.set(cell, token).unwrap());
+        let after = marked(&game, token);
+        assert!(after.contains(&cell));
+        for other in before {
+            let peer = other.row() == cell.row()
+                || other.column() == cell.column()
+                || Size::STANDARD.sector(other) == Size::STANDARD.sector(cell);
+            assert_eq!(after.contains(&other), !peer || other == cell);
         }
+
+        // The filled cell keeps its marks for when it is erased
+        game.fill_marks();
+        assert_eq!(marked(&game, token), after);
+
+        game.clear_marks();
+        assert!(game.marks(cell).is_empty());
+    }
+
+    #[test]
+    fn candidates_single() {
+        let mut candidates = Candidates::empty();
+        assert_eq!(candidates.single(), None);
+
+        candidates.insert(Token::Six);
+        assert_eq!(candidates.single(), Some(Token::Six));
+
+        candidates.insert(Token::Nine);
+        assert_eq!(candidates.single(), None);
+
+        candidates.remove(Token::Six);
+        assert_eq!(candidates.single(), Some(Token::Nine));
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(Size::from_side(4), Size::new(2, 2));
+        assert_eq!(Size::from_side(6), Size::new(2, 3));
+        assert_eq!(Size::from_side(12), Size::new(3, 4));
+        assert_eq!(Size::from_side(16), Size::new(4, 4));
+        assert_eq!(Size::from_side(25), Size::new(5, 5));
+        assert_eq!(Size::from_side(7), None);
+        assert_eq!(Size::from_side(36), None);
+
+        assert_eq!(Size::from_cells(81), Some(Size::STANDARD));
+        assert_eq!(Size::from_cells(36), Size::new(2, 3));
+        assert_eq!(Size::from_cells(80), None);
+
+        let size = Size::new(3, 2).unwrap();
+        assert_eq!(size.side(), 6);
+        assert_eq!(size.tokens().len(), 6);
+        assert_eq!(format!("{size:?}"), "6x6 (3x2 sectors)");
+        assert!(!size.contains(Cell::new(0, 6)));
+        assert_eq!(size.cell(size.index(Cell::new(4, 5))), Cell::new(4, 5));
+    }
+
+    #[test]
+    fn token_from_char() {
+        assert_eq!(Token::from_char('.'), Some(Token::None));
+        assert_eq!(Token::from_char('0'), Some(Token::None));
+        assert_eq!(Token::from_char('9'), Some(Token::Nine));
+        assert_eq!(Token::from_char('a'), Some(Token::Ten));
+        assert_eq!(Token::from_char('G'), Some(Token::Sixteen));
+        assert_eq!(Token::from_char('P'), Some(Token::TwentyFive));
+        assert_eq!(Token::from_char('Q'), None);
+        assert_eq!(Token::from_char('-'), None);
+        assert_eq!(Token::Sixteen.to_string(), "G");
     }
 
     #[test]
@@ -353,8 +1327,23 @@ mod tests {
             6,6,6,7,7,7,8,8,8,
         ];
 
-        for cell in BoardIndexer::new() {
-            assert_eq!(cell.sector(), jig[cell.index()]);
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(Size::STANDARD.sector(cell), jig[Size::STANDARD.index(cell)]);
+        }
+
+        #[rustfmt::skip]
+        let jig: [usize; 36] = [
+            0,0,0,1,1,1,
+            0,0,0,1,1,1,
+            2,2,2,3,3,3,
+            2,2,2,3,3,3,
+            4,4,4,5,5,5,
+            4,4,4,5,5,5,
+        ];
+
+        let size = Size::new(2, 3).unwrap();
+        for cell in BoardIndexer::new(size) {
+            assert_eq!(size.sector(cell), jig[size.index(cell)]);
         }
     }
 
@@ -373,53 +1362,279 @@ mod tests {
             6,7,8,6,7,8,6,7,8,
         ];
 
-        for cell in BoardIndexer::new() {
-            assert_eq!(cell.sector_index(), jig[cell.index()]);
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(
+                Size::STANDARD.sector_index(cell),
+                jig[Size::STANDARD.index(cell)]
+            );
         }
     }
 
     #[test]
-    fn transform_consistency() {
-        let mut board = ops::consistent_board();
-        transform::shift(&mut board, 2);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    fn display() {
+        let mut game = Game::new_empty(Size::new(2, 3).unwrap());
+        game.set_internal(Cell::new(0, 0), Token::One);
+        game.set_internal(Cell::new(5, 5), Token::Six);
+
+        let expected = "\
+┏━━━━━┯━━━━━┓
+┃1    │     ┃
+┃     │     ┃
+┠─────┼─────┨
+┃     │     ┃
+┃     │     ┃
+┠─────┼─────┨
+┃     │     ┃
+┃     │    6┃
+┗━━━━━┷━━━━━┛
+";
+        assert_eq!(game.to_string(), expected);
+    }
+
+    #[test]
+    fn display_jigsaw() {
+        let size = Size::new(2, 2).unwrap();
+        let regions =
+            Regions::new(size, &[0, 0, 0, 1, 2, 0, 1, 1, 2, 2, 3, 1, 2, 3, 3, 3]).unwrap();
+        let mut game = Game::with_regions(regions, vec![Token::None; 16]);
+        game.set_internal(Cell::new(0, 0), Token::One);
+        game.set_internal(Cell::new(3, 3), Token::Four);
+
+        let expected = "\
+┏━━━━━┳━┓
+┃1    ┃ ┃
+┣━┓ ┏━┛ ┃
+┃ ┃ ┃   ┃
+┃ ┗━╋━┓ ┃
+┃   ┃ ┃ ┃
+┃ ┏━┛ ┗━┫
+┃ ┃    4┃
+┗━┻━━━━━┛
+";
+        assert_eq!(game.to_string(), expected);
+    }
 
-        transform::rotate(&mut board);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn display_killer() {
+        let size = Size::new(2, 2).unwrap();
+        let cage = |sum, cells: &[(usize, usize)]| {
+            Cage::new(
+                sum,
+                cells
+                    .iter()
+                    .map(|(row, column)| Cell::new(*row, *column))
+                    .collect(),
+            )
+        };
+        let cages = Cages::new(
+            size,
+            vec![
+                cage(3, &[(0, 0), (0, 1)]),
+                cage(10, &[(1, 0), (1, 1), (2, 1), (2, 0)]),
+                cage(7, &[(0, 2), (0, 3)]),
+            ],
+        )
+        .unwrap();
+        let mut game = Game::new_empty(size).with_cages(cages);
+        game.set_internal(Cell::new(0, 0), Token::One);
+        game.set_internal(Cell::new(3, 3), Token::Four);
+
+        let expected = "\
+┏━━━━━┳━━━━━┓
+┃3    ┃7    ┃
+┃ 1   ┃     ┃
+┠─────╂─────┨
+┃10   ┃     ┃
+┃     ┃     ┃
+┣━━━━━╋━━━━━┫
+┃     ┃     ┃
+┃     ┃     ┃
+┠─────┨     ┃
+┃     ┃     ┃
+┃     ┃    4┃
+┗━━━━━┻━━━━━┛
+";
+        assert_eq!(game.to_string(), expected);
+    }
 
-        transform::mirror_columns(&mut board);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn jigsaw_masks() {
+        let regions = Regions::new(Size::STANDARD, &super::jigsaw_labels()).unwrap();
+        let mut game = Game::with_regions(regions, vec![Token::None; 81]);
+
+        // Both cells are in the irregular region 3, but not in the same row, column or sector
+        assert!(game.set(Cell::new(1, 1), Token::Two).unwrap());
+        assert!(!game.candidates(Cell::new(5, 2)).contains(Token::Two));
+        assert!(!game.set(Cell::new(5, 2), Token::Two).unwrap());
+        game.set_internal(Cell::new(5, 2), Token::None);
+        assert!(game.candidates(Cell::new(2, 2)).contains(Token::Two));
+    }
 
-        transform::mirror_rows(&mut board);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn transform_consistency() {
+        use rand::SeedableRng;
+
+        let size = Size::STANDARD;
+        let mut board = Game::from(ops::consistent_board()).board;
+        let check = |board: &[Token]| {
+            ops::assert_consistent(&Game::with_tokens(size, board.to_vec()));
+            assert_no_empty(board);
+        };
+
+        transform::rotate(&mut board, size);
+        check(&board);
+        transform::mirror_columns(&mut board, size);
+        check(&board);
+        transform::mirror_rows(&mut board, size);
+        check(&board);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(9);
+        for _ in 0..8 {
+            Transformation::random(size, &mut rng).apply(&mut board);
+            check(&board);
+        }
+    }
 
-        transform::swap_columns(&mut board, 1, 1);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn shuffle() {
+        use rand::SeedableRng;
 
-        transform::swap_rows(&mut board, 1, 1);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+        let game = Game::from(ops::consistent_board());
+        let (shuffled, _) = game.shuffle(12345, Symmetry::None);
+        ops::assert_consistent(&shuffled);
+        assert_no_empty(&shuffled.board);
+
+        let game = Game::new_solved(Size::new(3, 4).unwrap(), &mut rand::thread_rng());
+        let (shuffled, _) = game.shuffle(12345, Symmetry::None);
+        ops::assert_consistent(&shuffled);
+        assert_no_empty(&shuffled.board);
+
+        let regions = Regions::new(Size::STANDARD, &super::jigsaw_labels()).unwrap();
+        let game = Game::with_regions(regions.clone(), vec![Token::None; 81])
+            .solve(&mut rand::thread_rng())
+            .unwrap();
+        let (shuffled, _) = game.shuffle(12345, Symmetry::None);
+        assert!(shuffled.is_solved());
+        assert!(!shuffled.regions().is_standard());
+        assert_ne!(shuffled.regions(), &regions);
+
+        let game = Game::new_empty(Size::STANDARD)
+            .with_variant(Variant::Diagonal)
+            .with_variant(Variant::Windoku)
+            .solve(&mut rand::rngs::StdRng::seed_from_u64(15))
+            .unwrap();
+        for seed in 0..10 {
+            let (shuffled, _) = game.shuffle(seed, Symmetry::None);
+            assert!(shuffled.is_solved());
+            assert_eq!(shuffled.variants(), game.variants());
+        }
+    }
 
-        transform::swap_column_sector(&mut board, 1);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn transform() {
+        let puzzle = Game::from(ops::hard_board());
+        let (entry, marked) = (Cell::new(0, 1), Cell::new(0, 2));
+        let mut board = puzzle.clone();
+        board.set(entry, Token::Two).unwrap();
+        board.toggle_mark(marked, Token::Four);
+
+        // The entries and pencil marks of a board follow its puzzle
+        let (shuffled, transformation) = puzzle.shuffle(3, Symmetry::None);
+        let moved = board.transform(&transformation).unwrap();
+        let cell = transformation.cell(entry);
+        assert_eq!(moved.get(cell), transformation.token(Token::Two));
+        assert!(!moved.is_given(cell));
+        assert!(moved
+            .marks(transformation.cell(marked))
+            .contains(transformation.token(Token::Four)));
+        assert_eq!(shuffled.check(&moved).unwrap().entries(), 1);
+
+        let back = moved.transform(&transformation.inverse()).unwrap();
+        assert_eq!(format!("{back:?}"), format!("{board:?}"));
+        assert_eq!(back.marks(marked), board.marks(marked));
+
+        assert!(!transformation.is_dihedral());
+        let windoku = puzzle.with_variant(Variant::Windoku);
+        assert!(windoku.transform(&transformation).is_err());
+        assert!(windoku
+            .transform(&Transformation::mirror_rows(Size::STANDARD))
+            .is_ok());
+        let small = Game::new_empty(Size::new(2, 3).unwrap());
+        assert!(small.transform(&transformation).is_err());
+        assert!(small
+            .transform(&Transformation::transpose(small.size()))
+            .is_err());
+        // Regions that are not sectors can be transposed, as long as they stay connected
+        let size = small.size();
+        let labels = (0..size.cells())
+            .map(|index| index / size.side())
+            .collect::<Vec<_>>();
+        let jigsaw = Game::with_regions(Regions::new(size, &labels).unwrap(), small.board.clone());
+        let transposed = jigsaw.transform(&Transformation::transpose(size)).unwrap();
+        assert_eq!(
+            transposed.regions().region(Cell::new(0, 1)),
+            transposed.regions().region(Cell::new(1, 1))
+        );
+    }
 
-        transform::swap_row_sector(&mut board, 1);
-        ops::assert_consistent(&board.into());
-        assert_no_empty(&board);
+    #[test]
+    fn orbit() {
+        let center = Cell::new(4, 4);
+        let corner = Cell::new(0, 0);
+        let edge = Cell::new(0, 1);
+
+        assert_eq!(Symmetry::None.orbit(Size::STANDARD, edge), vec![edge]);
+        assert_eq!(
+            Symmetry::Dihedral.orbit(Size::STANDARD, center),
+            vec![center]
+        );
+        assert_eq!(Symmetry::Dihedral.orbit(Size::STANDARD, corner).len(), 4);
+        assert_eq!(Symmetry::Dihedral.orbit(Size::STANDARD, edge).len(), 8);
+        assert_eq!(
+            Symmetry::Diagonal.orbit(Size::STANDARD, Cell::new(3, 3)),
+            vec![Cell::new(3, 3)]
+        );
+        assert_eq!(
+            Symmetry::Rot180.orbit(Size::STANDARD, edge),
+            vec![edge, Cell::new(8, 7)]
+        );
+        assert_eq!(
+            Symmetry::Rot90.orbit(Size::STANDARD, edge),
+            vec![edge, Cell::new(8, 7), Cell::new(1, 8), Cell::new(7, 0)]
+        );
+        assert_eq!(
+            Symmetry::AntiDiagonal.orbit(Size::STANDARD, edge),
+            vec![edge, Cell::new(7, 8)]
+        );
     }
 
     #[test]
-    fn shuffle() {
-        let game = Game::from(ops::consistent_board());
-        game.shuffle(12345);
-        ops::assert_consistent(&game);
-        assert_no_empty(&game.board);
+    fn symmetric_shuffle() {
+        let symmetries = [
+            Symmetry::Rot180,
+            Symmetry::Rot90,
+            Symmetry::Horizontal,
+            Symmetry::Vertical,
+            Symmetry::Diagonal,
+            Symmetry::AntiDiagonal,
+            Symmetry::Dihedral,
+        ];
+
+        for symmetry in &symmetries {
+            let mut game = Game::from(ops::consistent_board());
+            for cell in &[Cell::new(0, 1), Cell::new(2, 5), Cell::new(3, 3)] {
+                for cell in symmetry.orbit(Size::STANDARD, *cell) {
+                    game.set_internal(cell, Token::None);
+                }
+            }
+
+            let (shuffled, _) = game.shuffle(54321, *symmetry);
+            for cell in BoardIndexer::new(Size::STANDARD) {
+                let empty = shuffled.get(cell) == Token::None;
+                for other in symmetry.orbit(Size::STANDARD, cell) {
+                    assert_eq!(shuffled.get(other) == Token::None, empty);
+                }
+            }
+        }
     }
 
     #[test]
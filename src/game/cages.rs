@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use super::{regions, Candidates, Cell, Constraint, Game, Size, Token};
+
+// Killer Sudoku: groups of connected cells whose tokens must add up to the sum of the group, without
+// repeating. Shared between the copies of a game, like the regions
+#[derive(Clone, Eq, PartialEq)]
+pub struct Cages {
+    size: Size,
+    map: Arc<[Option<usize>]>,
+    list: Arc<[Cage]>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cage {
+    sum: usize,
+    cells: Vec<Cell>,
+}
+
+impl Cage {
+    pub fn new(sum: usize, mut cells: Vec<Cell>) -> Self {
+        cells.sort();
+        Self { sum, cells }
+    }
+
+    #[inline]
+    pub fn sum(&self) -> usize {
+        self.sum
+    }
+
+    // In reading order, so the first one is where the sum is written
+    #[inline]
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // Tokens in the cells of the cage other than the given one, along with their sum and the number
+    // of empty cells. Returns `None` if the other cells already repeat a token
+    fn tally(&self, game: &Game, cell: Cell) -> Option<(u32, usize, usize)> {
+        let mut used = 0;
+        let mut sum = 0;
+        let mut empty = 0;
+        for other in self.cells.iter().filter(|other| **other != cell) {
+            let token = game.get(*other);
+            if token == Token::None {
+                empty += 1;
+            } else if used & token.mask() != 0 {
+                return None;
+            } else {
+                used |= token.mask();
+                sum += token as usize;
+            }
+        }
+        Some((used, sum, empty))
+    }
+}
+
+impl Cages {
+    // Cages are put in reading order of their first cell. Returns `None` if a cage is empty, leaves
+    // the board, overlaps another one, is not connected, or has a sum its cells cannot reach.
+    // Cells do not need to be covered by a cage
+    pub fn new(size: Size, mut cages: Vec<Cage>) -> Option<Self> {
+        cages.sort_by_key(|cage| cage.cells.first().copied());
+
+        let mut map = vec![None; size.cells()];
+        for (index, cage) in cages.iter().enumerate() {
+            if cage.cells.is_empty()
+                || !reachable(size, 0, cage.cells.len(), cage.sum)
+                || regions::components(size, &cage.cells).len() != 1
+            {
+                return None;
+            }
+
+            for cell in &cage.cells {
+                if !size.contains(*cell) || map[size.index(*cell)].replace(index).is_some() {
+                    return None;
+                }
+            }
+        }
+
+        Some(Self {
+            size,
+            map: map.into(),
+            list: cages.into(),
+        })
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub fn index(&self, cell: Cell) -> Option<usize> {
+        self.map[self.size.index(cell)]
+    }
+
+    #[inline]
+    pub fn cage(&self, cell: Cell) -> Option<&Cage> {
+        self.index(cell).map(|index| &self.list[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cage> {
+        self.list.iter()
+    }
+
+    // Characters needed to write the largest sum
+    pub fn width(&self) -> usize {
+        self.list
+            .iter()
+            .map(|cage| cage.sum.to_string().len())
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+// Whether the token still lets the empty cells of the cage reach its sum
+fn fits(size: Size, (used, sum, empty): (u32, usize, usize), token: Token, cage: &Cage) -> bool {
+    let sum = sum + token as usize;
+    used & token.mask() == 0
+        && sum <= cage.sum
+        && reachable(size, used | token.mask(), empty, cage.sum - sum)
+}
+
+// Whether `count` distinct tokens missing from `used` can add up to `sum`, judging by the smallest
+// and largest of them
+fn reachable(size: Size, used: u32, count: usize, sum: usize) -> bool {
+    let free = size
+        .tokens()
+        .iter()
+        .filter(|token| used & token.mask() == 0)
+        .map(|token| *token as usize);
+    let lowest = free.clone().take(count).sum::<usize>();
+    let highest = free.clone().rev().take(count).sum::<usize>();
+    count <= free.count() && lowest <= sum && sum <= highest
+}
+
+// Whether each cell can take one of its candidates, all of them distinct and missing from `used`,
+// so that they add up to `sum`
+fn completes(cells: &[Candidates], used: u32, sum: usize) -> bool {
+    match cells.split_first() {
+        None => sum == 0,
+        Some((first, rest)) => (*first & !Candidates::from_mask(used)).iter().any(|token| {
+            let value = token as usize;
+            value <= sum && completes(rest, used | token.mask(), sum - value)
+        }),
+    }
+}
+
+impl Constraint for Cages {
+    fn name(&self) -> &'static str {
+        "Killer cages"
+    }
+
+    fn allows(&self, game: &Game, cell: Cell, token: Token) -> bool {
+        match self.cage(cell) {
+            Some(cage) => cage
+                .tally(game, cell)
+                .is_some_and(|tally| fits(self.size, tally, token, cage)),
+            None => true,
+        }
+    }
+
+    // Keeps the candidates that leave a combination for the rest of the cage, where each of the
+    // other empty cells takes one of the candidates its row, column and region leave
+    fn eliminate(&self, game: &Game, cell: Cell, candidates: Candidates) -> Candidates {
+        let Some(cage) = self.cage(cell) else {
+            return candidates;
+        };
+        let Some(tally) = cage.tally(game, cell) else {
+            return Candidates::empty();
+        };
+
+        let mut others = cage
+            .cells
+            .iter()
+            .filter(|other| **other != cell && game.get(**other) == Token::None)
+            .map(|other| game.unit_candidates(*other))
+            .collect::<Vec<_>>();
+        others.sort_by_key(|candidates| candidates.len());
+
+        let mut kept = candidates;
+        for token in candidates.iter() {
+            let (used, sum, _) = tally;
+            if !fits(self.size, tally, token, cage)
+                || !completes(
+                    &others,
+                    used | token.mask(),
+                    cage.sum - sum - token as usize,
+                )
+            {
+                kept.remove(token);
+            }
+        }
+        kept
+    }
+}
+
+// Each cage as its sum and its cells, as in 10=r1c1,r1c2;7=r1c3
+impl std::fmt::Display for Cages {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, cage) in self.list.iter().enumerate() {
+            if index > 0 {
+                write!(fmt, ";")?;
+            }
+            write!(fmt, "{}=", cage.sum)?;
+            for (index, cell) in cage.cells.iter().enumerate() {
+                if index > 0 {
+                    write!(fmt, ",")?;
+                }
+                write!(fmt, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Cages {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{:?}: {self}", self.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cage, Cages};
+    use crate::game::{Cell, Constraint, Game, Size, Token};
+
+    fn cages() -> Cages {
+        let size = Size::new(2, 2).unwrap();
+        Cages::new(
+            size,
+            vec![
+                Cage::new(3, vec![Cell::new(0, 0), Cell::new(0, 1)]),
+                Cage::new(
+                    10,
+                    vec![
+                        Cell::new(1, 0),
+                        Cell::new(1, 1),
+                        Cell::new(2, 1),
+                        Cell::new(2, 0),
+                    ],
+                ),
+                Cage::new(7, vec![Cell::new(0, 2), Cell::new(0, 3)]),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn new() {
+        let cages = cages();
+        assert_eq!(cages.iter().count(), 3);
+        assert_eq!(cages.cage(Cell::new(0, 2)).unwrap().sum(), 7);
+        assert_eq!(cages.index(Cell::new(2, 0)), Some(2));
+        assert_eq!(cages.cage(Cell::new(3, 3)), None);
+        assert_eq!(
+            cages.cage(Cell::new(2, 1)).unwrap().cells()[0],
+            Cell::new(1, 0)
+        );
+        assert_eq!(
+            cages.to_string(),
+            "3=r1c1,r1c2;7=r1c3,r1c4;10=r2c1,r2c2,r3c1,r3c2"
+        );
+
+        let size = Size::new(2, 2).unwrap();
+        let cage = |sum, cells: &[(usize, usize)]| {
+            Cage::new(
+                sum,
+                cells
+                    .iter()
+                    .map(|(row, column)| Cell::new(*row, *column))
+                    .collect(),
+            )
+        };
+        assert!(Cages::new(size, vec![cage(3, &[])]).is_none());
+        assert!(Cages::new(size, vec![cage(3, &[(0, 0), (1, 1)])]).is_none());
+        assert!(Cages::new(size, vec![cage(2, &[(0, 0), (0, 1)])]).is_none());
+        assert!(Cages::new(size, vec![cage(8, &[(0, 0), (0, 1)])]).is_none());
+        assert!(Cages::new(size, vec![cage(4, &[(0, 3), (0, 4)])]).is_none());
+        assert!(Cages::new(
+            size,
+            vec![cage(3, &[(0, 0), (0, 1)]), cage(5, &[(0, 1), (0, 2)])]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn sums() {
+        let cages = cages();
+        let mut game = Game::new_empty(cages.size()).with_cages(cages.clone());
+
+        // Only 1 and 2 add up to 3
+        let candidates = game.candidates(Cell::new(0, 0));
+        assert_eq!(
+            candidates.iter().collect::<Vec<_>>(),
+            vec![Token::One, Token::Two]
+        );
+
+        // The last cell has to make up the rest of the sum
+        assert!(game.set(Cell::new(1, 0), Token::Four).unwrap());
+        assert!(game.set(Cell::new(1, 1), Token::One).unwrap());
+        assert!(!game.set(Cell::new(2, 0), Token::Four).unwrap());
+        assert!(game.set(Cell::new(2, 0), Token::Two).unwrap());
+        assert_eq!(
+            game.candidates(Cell::new(2, 1)).single(),
+            Some(Token::Three)
+        );
+        assert!(!cages.allows(&game, Cell::new(2, 1), Token::Four));
+
+        // Neither row, column nor sector stops the repeat, but the cage does
+        let mut game = Game::new_empty(cages.size()).with_cages(cages);
+        assert!(game.set(Cell::new(1, 0), Token::One).unwrap());
+        assert!(!game.set(Cell::new(2, 1), Token::One).unwrap());
+        assert!(game.candidates(Cell::new(1, 1)).is_empty());
+    }
+}
@@ -0,0 +1,621 @@
+use super::{ops, transform, Game, Regions, Size, Token};
+
+// Columns whose order is still open after the rows picked so far. Stacks in the same group can still
+// swap, and so can the columns in the same part of a stack
+type Stack = Vec<Vec<usize>>;
+type Columns = Vec<Vec<Stack>>;
+
+// What a cell of the row being picked sorts by. Tokens seen for the first time in the row before are
+// labelled in the order their columns end up in, so until those are placed they only point at them.
+// New tokens take the next free label wherever they end up, so they sort after all the others
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Key {
+    Empty,
+    Label(u8),
+    Pending(usize),
+    New,
+}
+
+// The lexicographically smallest board among those `Game::shuffle` can reach, reading empty cells
+// as 0 and relabelling the tokens in the order they first show up. Standard boards take any order
+// of the bands and of the rows in them, the same for stacks and columns, and the transpose when the
+// sectors are square. Jigsaw regions and variants only keep to the mirrors and the transpose, and
+// other constraints such as Killer cages tie the tokens down, so those boards are left as they are.
+// The search follows each token from the row it first shows up in, so boards that break a rule
+// only keep to the mirrors and the transpose as well
+pub fn canonical(game: &Game) -> Game {
+    if game.constraints().len() > game.variants().len() {
+        return game.clone();
+    }
+    if !game.regions().is_standard() || !game.variants().is_empty() || !ops::valid(game) {
+        return dihedral(game);
+    }
+
+    let size = game.size();
+    let board = game
+        .board
+        .iter()
+        .map(|token| *token as u8)
+        .collect::<Vec<_>>();
+    let mut search = Search {
+        size,
+        board,
+        best: None,
+    };
+    search.start();
+    if size.is_square() {
+        transform::rotate(&mut search.board, size);
+        search.start();
+    }
+
+    let best = search.best.expect("Every board has an arrangement");
+    Game::with_tokens(size, best.iter().map(Token::from).collect())
+}
+
+// FNV-1a over the canonical board as written by `Debug`, which does not change between runs or
+// builds the way the hashers of the standard library might
+pub fn fingerprint(game: &Game) -> u64 {
+    format!("{:?}", canonical(game))
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+// Tries the eight mirrors and transposes, comparing the region labels before the tokens. The
+// variants are put back in a fixed order, so that they are written out the same way
+fn dihedral(game: &Game) -> Game {
+    let size = game.size();
+    let transposes: &[bool] = if size.is_square() || !game.regions().is_standard() {
+        &[false, true]
+    } else {
+        &[false]
+    };
+
+    let mut best: Option<(Vec<usize>, Vec<Token>)> = None;
+    for transpose in transposes {
+        for mirror in 0..4 {
+            let mut board = game.board.clone();
+            let mut labels = game.regions().labels();
+            if *transpose {
+                transform::rotate(&mut board, size);
+                transform::rotate(&mut labels, size);
+            }
+            if mirror & 1 != 0 {
+                transform::mirror_rows(&mut board, size);
+                transform::mirror_rows(&mut labels, size);
+            }
+            if mirror & 2 != 0 {
+                transform::mirror_columns(&mut board, size);
+                transform::mirror_columns(&mut labels, size);
+            }
+
+            let labels = Regions::new(size, &labels)
+                .expect("Mirrors keep the regions connected")
+                .labels();
+            let board = relabel(size, &board);
+            if best
+                .as_ref()
+                .is_none_or(|best| (&labels, &board) < (&best.0, &best.1))
+            {
+                best = Some((labels, board));
+            }
+        }
+    }
+
+    let (labels, board) = best.expect("There is always the board itself");
+    let regions = Regions::new(size, &labels).expect("Mirrors keep the regions connected");
+    let mut variants = game.variants().to_vec();
+    variants.sort();
+    variants
+        .into_iter()
+        .fold(Game::with_regions(regions, board), |game, variant| {
+            game.with_variant(variant)
+        })
+}
+
+// Tokens renamed in the order they first show up
+fn relabel(size: Size, board: &[Token]) -> Vec<Token> {
+    let mut labels = vec![Token::None; size.side() + 1];
+    let mut next = 0;
+    board
+        .iter()
+        .map(|token| {
+            if *token != Token::None && labels[*token as usize] == Token::None {
+                labels[*token as usize] = size.tokens()[next];
+                next += 1;
+            }
+            labels[*token as usize]
+        })
+        .collect()
+}
+
+struct Search {
+    size: Size,
+    board: Vec<u8>,
+    best: Option<Vec<u8>>,
+}
+
+#[derive(Clone)]
+struct State {
+    rows: Vec<usize>,
+    columns: Columns,
+    labels: Vec<u8>,
+    next: u8,
+    string: Vec<u8>,
+}
+
+// The row being picked, with the keys of its cells and the columns whose tokens in the row before
+// still wait for their labels. Those labels are in the string of the row before, by position
+struct Row {
+    width: usize,
+    keys: Vec<Key>,
+    pending: Vec<bool>,
+    previous: Vec<u8>,
+}
+
+impl Row {
+    fn involves<'a>(&self, mut columns: impl Iterator<Item = &'a usize>) -> bool {
+        columns.any(|column| self.pending[*column] || matches!(self.keys[*column], Key::Pending(_)))
+    }
+}
+
+// The columns being placed for a row, left to right, along with the string so far. The group under
+// the cursor holds a single stack, and so does the group of each column placed ahead of it
+#[derive(Clone)]
+struct Walk {
+    columns: Columns,
+    group: usize,
+    part: usize,
+    positions: Vec<Option<usize>>,
+    next: u8,
+    string: Vec<u8>,
+}
+
+impl Search {
+    fn start(&mut self) {
+        let side = self.size.side();
+        let width = self.size.sector_columns();
+        let stacks = (0..side / width)
+            .map(|stack| vec![(stack * width..(stack + 1) * width).collect()])
+            .collect();
+
+        self.search(&State {
+            rows: Vec::with_capacity(side),
+            columns: vec![stacks],
+            labels: vec![0; side + 1],
+            next: 1,
+            string: Vec::with_capacity(self.size.cells()),
+        });
+    }
+
+    // Picks the next row among those the bands allow. A first walk over the candidates finds the
+    // smallest string they can make, and a second goes on from each placement of the columns that
+    // makes it, one at a time
+    fn search(&mut self, state: &State) {
+        let side = self.size.side();
+        if state.rows.len() == side {
+            if self.best.as_ref().is_none_or(|best| state.string < *best) {
+                self.best = Some(state.string.clone());
+            }
+            return;
+        }
+
+        // Only the strings that can still match the best board so far are worth finding
+        let start = state.string.len();
+        let mut bound = match &self.best {
+            Some(best) if best[..start] < state.string[..] => return,
+            Some(best) if best[..start] == state.string[..] => {
+                Some(best[start..start + side].to_vec())
+            }
+            _ => None,
+        };
+
+        let height = self.size.sector_rows();
+        let candidates = if state.rows.len().is_multiple_of(height) {
+            (0..side)
+                .filter(|row| {
+                    !state
+                        .rows
+                        .iter()
+                        .any(|other| other / height == row / height)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let band = state.rows[state.rows.len() - 1] / height;
+            (band * height..(band + 1) * height)
+                .filter(|row| !state.rows.contains(row))
+                .collect()
+        };
+
+        // Swapping two rows that read the same, or two bands that hold the same rows, leaves the
+        // board as it is, so only the first of them needs to be tried
+        let line = |row: usize| &self.board[row * side..(row + 1) * side];
+        let band = |row: usize| {
+            let band = row / height * height;
+            let mut lines = (band..band + height).map(line).collect::<Vec<_>>();
+            lines.sort_unstable();
+            lines
+        };
+        let mut rows: Vec<(usize, Row)> = Vec::new();
+        for row in candidates {
+            if !rows.iter().any(|(other, _)| {
+                line(*other) == line(row)
+                    && (other / height == row / height || band(*other) == band(row))
+            }) {
+                rows.push((row, self.row(state, row)));
+            }
+        }
+
+        let mut found = false;
+        for (_, row) in &rows {
+            walk(row, Walk::new(state), &mut bound, &mut |_| found = true);
+        }
+        if !found {
+            return;
+        }
+
+        for (index, row) in &rows {
+            walk(row, Walk::new(state), &mut bound.clone(), &mut |walk| {
+                let next = self.advance(state, *index, row, walk);
+                self.search(&next);
+            });
+        }
+    }
+
+    // Empty cells come first, then the tokens already relabelled, then those of the row before in
+    // the order their columns end up in, then the new ones
+    fn row(&self, state: &State, row: usize) -> Row {
+        let side = self.size.side();
+        let previous = state
+            .rows
+            .last()
+            .map(|last| &self.board[last * side..(last + 1) * side]);
+        let pending = (0..side)
+            .map(|column| {
+                previous.is_some_and(|previous| {
+                    previous[column] != 0 && state.labels[usize::from(previous[column])] == 0
+                })
+            })
+            .collect();
+        let keys = self.board[row * side..(row + 1) * side]
+            .iter()
+            .map(|token| match (*token, state.labels[usize::from(*token)]) {
+                (0, _) => Key::Empty,
+                (_, 0) => previous
+                    .and_then(|previous| previous.iter().position(|other| other == token))
+                    .map_or(Key::New, Key::Pending),
+                (_, label) => Key::Label(label),
+            })
+            .collect();
+
+        Row {
+            width: self.size.sector_columns(),
+            keys,
+            pending,
+            previous: state.string[state.string.len().saturating_sub(side)..].to_vec(),
+        }
+    }
+
+    // Labels the tokens of the row before where their columns ended up
+    fn advance(&self, state: &State, index: usize, row: &Row, walk: Walk) -> State {
+        let side = self.size.side();
+        let mut labels = state.labels.clone();
+        if let Some(last) = state.rows.last() {
+            for (column, position) in walk.positions.iter().enumerate() {
+                if row.pending[column] {
+                    let position = position.expect("Every column waiting for a label is placed");
+                    labels[usize::from(self.board[last * side + column])] = row.previous[position];
+                }
+            }
+        }
+
+        let mut rows = state.rows.clone();
+        rows.push(index);
+        let mut string = state.string.clone();
+        string.extend_from_slice(&walk.string);
+        State {
+            rows,
+            columns: walk.columns,
+            labels,
+            next: walk.next,
+            string,
+        }
+    }
+}
+
+// Places the columns for a row. Where the row settles the order of tied columns, each order that
+// can still match the bound is tried, and the other ties stay open for the rows to come. Each walk
+// that makes it to the end tightens the bound before it is handed on
+fn walk(row: &Row, mut walk: Walk, bound: &mut Option<Vec<u8>>, finish: &mut dyn FnMut(Walk)) {
+    loop {
+        if bound
+            .as_ref()
+            .is_some_and(|bound| walk.string[..] > bound[..walk.string.len()])
+        {
+            return;
+        }
+
+        let Some(group) = walk.columns.get(walk.group) else {
+            if bound.as_ref().is_none_or(|bound| walk.string < *bound) {
+                *bound = Some(walk.string.clone());
+            }
+            finish(walk);
+            return;
+        };
+
+        if group.len() > 1 {
+            if row.involves(group.iter().flatten().flatten()) {
+                for index in 0..group.len() {
+                    let mut walk = walk.clone();
+                    let stack = walk.columns[walk.group].remove(index);
+                    walk.columns.insert(walk.group, vec![stack]);
+                    self::walk(row, walk, bound, finish);
+                }
+                return;
+            }
+
+            let (groups, keys) = refine(group, &row.keys);
+            let count = groups.len();
+            walk.columns.splice(walk.group..=walk.group, groups);
+            walk.group += count;
+            walk.extend(keys);
+            continue;
+        }
+
+        let Some(part) = group[0].get(walk.part) else {
+            walk.group += 1;
+            walk.part = 0;
+            continue;
+        };
+
+        if part.len() == 1 {
+            let column = part[0];
+            walk.place(row, column);
+            walk.part += 1;
+        } else if row.involves(part.iter()) {
+            for index in 0..part.len() {
+                let mut walk = walk.clone();
+                let stack = &mut walk.columns[walk.group][0];
+                let column = stack[walk.part].remove(index);
+                stack.insert(walk.part, vec![column]);
+                self::walk(row, walk, bound, finish);
+            }
+            return;
+        } else {
+            let (parts, keys) = split(part, &row.keys);
+            let count = parts.len();
+            let stack = &mut walk.columns[walk.group][0];
+            stack.splice(walk.part..=walk.part, parts);
+            walk.part += count;
+            walk.extend(keys);
+        }
+    }
+}
+
+impl Walk {
+    fn new(state: &State) -> Self {
+        let side = state.labels.len() - 1;
+        Self {
+            columns: state.columns.clone(),
+            group: 0,
+            part: 0,
+            positions: vec![None; side],
+            next: state.next,
+            string: Vec::with_capacity(side),
+        }
+    }
+
+    fn place(&mut self, row: &Row, column: usize) {
+        let position = self.string.len();
+        if row.pending[column] {
+            self.positions[column] = Some(position);
+        }
+        let label = match row.keys[column] {
+            Key::Pending(first) => row.previous[self.locate(row, first)],
+            key => self.label(key),
+        };
+        self.string.push(label);
+    }
+
+    fn extend(&mut self, keys: Vec<Key>) {
+        for key in keys {
+            let label = self.label(key);
+            self.string.push(label);
+        }
+    }
+
+    fn label(&mut self, key: Key) -> u8 {
+        match key {
+            Key::Empty => 0,
+            Key::Label(label) => label,
+            Key::New => {
+                self.next += 1;
+                self.next - 1
+            }
+            Key::Pending(_) => unreachable!("Columns waiting for a label are placed one at a time"),
+        }
+    }
+
+    // Where a column holding a token of the row before ends up. If it is still free, it takes the
+    // first place left to it, which gives the token the smallest label it can get
+    fn locate(&mut self, row: &Row, column: usize) -> usize {
+        if let Some(position) = self.positions[column] {
+            return position;
+        }
+
+        let holds = |stack: &Stack| stack.iter().flatten().any(|other| *other == column);
+        let (group, stack) = (self.group..self.columns.len())
+            .find_map(|group| {
+                let stack = self.columns[group].iter().position(holds)?;
+                Some((group, stack))
+            })
+            .expect("Columns waiting for a label are ahead of the cursor");
+        if self.columns[group].len() > 1 {
+            let stack = self.columns[group].remove(stack);
+            self.columns.insert(group, vec![stack]);
+        }
+
+        let parts = &mut self.columns[group][0];
+        let part = parts
+            .iter()
+            .position(|part| part.contains(&column))
+            .expect("The stack holds the column");
+        if parts[part].len() > 1 {
+            parts[part].retain(|other| *other != column);
+            parts.insert(part, vec![column]);
+        }
+
+        let position = self.columns[..group]
+            .iter()
+            .map(|group| group.len() * row.width)
+            .sum::<usize>()
+            + self.columns[group][0][..part]
+                .iter()
+                .map(Vec::len)
+                .sum::<usize>();
+        self.positions[column] = Some(position);
+        position
+    }
+}
+
+// Sorts the stacks of a group by their keys, splitting the group where the keys differ. Returns the
+// groups and the keys of the row in their new order
+fn refine(group: &[Stack], keys: &[Key]) -> (Vec<Vec<Stack>>, Vec<Key>) {
+    let mut stacks = group
+        .iter()
+        .map(|stack| {
+            let mut split = Vec::new();
+            let mut signature = Vec::new();
+            for part in stack {
+                let (parts, keys) = self::split(part, keys);
+                split.extend(parts);
+                signature.extend(keys);
+            }
+            (signature, split)
+        })
+        .collect::<Vec<_>>();
+    stacks.sort_by(|first, second| first.0.cmp(&second.0));
+
+    let groups = stacks
+        .chunk_by(|first, second| first.0 == second.0)
+        .map(|tied| tied.iter().map(|(_, split)| split.clone()).collect())
+        .collect();
+    let string = stacks
+        .into_iter()
+        .flat_map(|(signature, _)| signature)
+        .collect();
+    (groups, string)
+}
+
+// The same for the columns of a part of a stack
+fn split(part: &[usize], keys: &[Key]) -> (Vec<Vec<usize>>, Vec<Key>) {
+    let mut part = part.to_vec();
+    part.sort_by_key(|column| keys[*column]);
+    let parts = part
+        .chunk_by(|first, second| keys[*first] == keys[*second])
+        .map(<[usize]>::to_vec)
+        .collect();
+    (parts, part.iter().map(|column| keys[*column]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::game::{jigsaw_labels, ops, Game, Regions, Size, Symmetry, Token, Variant};
+
+    fn assert_same(game: &Game, symmetry: Symmetry) {
+        let canonical = game.canonical();
+        assert_eq!(
+            format!("{:?}", canonical.canonical()),
+            format!("{canonical:?}")
+        );
+        for seed in 0..8 {
+            let (shuffled, _) = game.shuffle(seed, symmetry);
+            assert_eq!(
+                format!("{:?}", shuffled.canonical()),
+                format!("{canonical:?}")
+            );
+            assert_eq!(shuffled.fingerprint(), game.fingerprint());
+        }
+    }
+
+    #[test]
+    fn standard() {
+        let puzzle = Game::from(ops::hard_board());
+        assert_same(&puzzle, Symmetry::None);
+        assert_same(&puzzle, Symmetry::Rot180);
+        assert_eq!(puzzle.canonical().count_solutions(2), 1);
+        assert_eq!(puzzle.canonical().clues(), puzzle.clues());
+
+        let solved = Game::from(ops::consistent_board());
+        assert_same(&solved, Symmetry::None);
+        assert_ne!(solved.fingerprint(), puzzle.fingerprint());
+
+        // Removing a single clue makes another puzzle
+        let mut board = ops::hard_board();
+        board[0] = 0;
+        assert_ne!(Game::from(board).fingerprint(), puzzle.fingerprint());
+
+        let empty = Game::new_empty(Size::STANDARD);
+        assert_eq!(empty.canonical().clues(), 0);
+    }
+
+    #[test]
+    fn rectangular() {
+        let size = Size::new(3, 4).unwrap();
+        let solved = Game::new_solved(size, &mut rand::rngs::StdRng::seed_from_u64(3));
+        let mut board = solved.board.clone();
+        for (index, token) in board.iter_mut().enumerate() {
+            if index % 3 != 0 {
+                *token = Token::None;
+            }
+        }
+        let game = Game::with_tokens(size, board);
+        assert_same(&game, Symmetry::None);
+        assert_eq!(game.canonical().size(), size);
+    }
+
+    // The columns of a full row can go in any order, which are settled by the rows after it rather
+    // than tried one by one
+    #[test]
+    fn large() {
+        let size = Size::new(4, 4).unwrap();
+        let solved = Game::new_solved(size, &mut rand::rngs::StdRng::seed_from_u64(11));
+        assert_same(&solved, Symmetry::None);
+        assert!(solved.canonical().is_solved());
+
+        let empty = Game::new_empty(size);
+        assert_eq!(empty.fingerprint(), empty.canonical().fingerprint());
+    }
+
+    #[test]
+    fn shapes() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let regions = Regions::new(Size::STANDARD, &jigsaw_labels()).unwrap();
+        let jigsaw = Game::with_regions(regions, vec![Token::None; 81])
+            .solve(&mut rng)
+            .unwrap();
+        assert_same(&jigsaw, Symmetry::None);
+        assert!(!jigsaw.canonical().regions().is_standard());
+
+        let variants = Game::new_empty(Size::STANDARD)
+            .with_variant(Variant::Windoku)
+            .with_variant(Variant::Diagonal)
+            .solve(&mut rng)
+            .unwrap();
+        assert_same(&variants, Symmetry::None);
+        assert_eq!(
+            variants.canonical().variants(),
+            &[Variant::Diagonal, Variant::Windoku]
+        );
+    }
+
+    #[test]
+    fn killer() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let killer = Game::from(ops::consistent_board()).killer(&mut rng);
+        assert_eq!(format!("{:?}", killer.canonical()), format!("{killer:?}"));
+    }
+}
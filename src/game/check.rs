@@ -0,0 +1,173 @@
+use super::{ops, Cell, Game, Token};
+use crate::error::Error;
+use crate::index::BoardIndexer;
+
+// Mistakes on a board played from a puzzle. Wrong entries differ from the solution, and
+// inconsistent ones also break a rule with the rest of the board, which the player can see
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Check {
+    entries: usize,
+    wrong: Vec<Cell>,
+    inconsistent: Vec<Cell>,
+}
+
+impl Check {
+    // Tokens the player placed
+    #[inline]
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    #[inline]
+    pub fn wrong(&self) -> &[Cell] {
+        &self.wrong
+    }
+
+    #[inline]
+    pub fn inconsistent(&self) -> &[Cell] {
+        &self.inconsistent
+    }
+
+    // The puzzle has a single solution, so the board still has one as long as every entry agrees
+    // with it. Wrong entries can leave it without one before any rule is broken
+    #[inline]
+    pub fn is_solvable(&self) -> bool {
+        self.wrong.is_empty()
+    }
+}
+
+// Takes the puzzle as it was given and the board as the player left it, which must keep its givens.
+// Fails unless the puzzle has a single solution to compare against
+pub fn check(puzzle: &Game, board: &Game) -> Result<Check, Error> {
+    if board.regions() != puzzle.regions() {
+        return error!("the board does not have the shape of the puzzle");
+    }
+
+    let mut entries = Vec::new();
+    for cell in BoardIndexer::new(puzzle.size()) {
+        let (given, token) = (puzzle.get(cell), board.get(cell));
+        if given == Token::None {
+            if token != Token::None {
+                entries.push((cell, token));
+            }
+        } else if token != given {
+            return error!("{cell} does not hold the {given} given by the puzzle");
+        }
+    }
+
+    let solutions = puzzle.solutions(2);
+    let [solution] = &solutions[..] else {
+        return error!("the puzzle does not have a single solution");
+    };
+
+    // The board is played out on the puzzle, so that it follows the same constraints
+    let mut played = puzzle.clone();
+    for (cell, token) in &entries {
+        played.set_internal(*cell, *token);
+    }
+
+    Ok(Check {
+        entries: entries.len(),
+        wrong: entries
+            .iter()
+            .filter(|(cell, token)| solution.get(*cell) != *token)
+            .map(|(cell, _)| *cell)
+            .collect(),
+        inconsistent: entries
+            .iter()
+            .filter(|(cell, token)| !ops::consistent(&played, *cell, *token))
+            .map(|(cell, _)| *cell)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{ops, Cell, Game, History, Token, Variant};
+
+    #[test]
+    fn check() {
+        let puzzle = Game::from(ops::hard_board());
+        let solution = &puzzle.solutions(1)[0];
+        let (right, wrong, clash) = (Cell::new(0, 1), Cell::new(0, 2), Cell::new(1, 0));
+
+        let mut board = puzzle.clone();
+        board.set(right, solution.get(right)).unwrap();
+        let check = puzzle.check(&board).unwrap();
+        assert_eq!(check.entries(), 1);
+        assert!(check.wrong().is_empty());
+        assert!(check.is_solvable());
+
+        // A token that breaks no rule can still be wrong
+        let token = *puzzle
+            .candidates(wrong)
+            .iter()
+            .filter(|token| *token != solution.get(wrong) && *token != solution.get(right))
+            .collect::<Vec<_>>()
+            .first()
+            .unwrap();
+        assert!(board.set(wrong, token).unwrap());
+        let check = puzzle.check(&board).unwrap();
+        assert_eq!(check.wrong(), &[wrong]);
+        assert!(check.inconsistent().is_empty());
+        assert!(!check.is_solvable());
+
+        // The 8 given in r1c1 is in the same sector
+        assert!(!board.set(clash, Token::Eight).unwrap());
+        let check = puzzle.check(&board).unwrap();
+        assert_eq!(check.wrong(), &[wrong, clash]);
+        assert_eq!(check.inconsistent(), &[clash]);
+    }
+
+    #[test]
+    fn invalid() {
+        let puzzle = Game::from(ops::hard_board());
+        let mut board = puzzle.clone();
+        board.set_internal(Cell::new(0, 0), Token::One);
+        assert!(puzzle.check(&board).is_err());
+
+        let empty = Game::new_empty(puzzle.size());
+        assert!(empty.check(&empty).is_err());
+        // The rules of the puzzle are the ones checked against
+        assert!(puzzle
+            .check(&puzzle.clone().with_variant(Variant::Windoku))
+            .is_ok());
+    }
+
+    #[test]
+    fn first_mistake() {
+        let puzzle = Game::from(ops::hard_board());
+        let solution = &puzzle.solutions(1)[0];
+        let mut history = History::new(puzzle.clone());
+        assert_eq!(history.first_mistake().unwrap(), None);
+
+        let cells = [Cell::new(0, 1), Cell::new(0, 2), Cell::new(0, 3)];
+        let wrong = |cell: Cell| {
+            puzzle
+                .candidates(cell)
+                .iter()
+                .find(|token| *token != solution.get(cell))
+                .unwrap()
+        };
+        history.set(cells[0], solution.get(cells[0])).unwrap();
+        history.set(cells[1], wrong(cells[1])).unwrap();
+        history.set(cells[2], wrong(cells[2])).unwrap();
+        assert_eq!(history.check().unwrap().wrong(), &cells[1..]);
+        assert_eq!(history.first_mistake().unwrap(), Some(2));
+
+        // Fixing the first mistake moves on to the next one
+        history.set(cells[1], solution.get(cells[1])).unwrap();
+        assert_eq!(history.first_mistake().unwrap(), Some(3));
+        assert_eq!(history.start().to_string(), puzzle.to_string());
+
+        // A mark toggled on the wrong token leaves it in place, so going back past the mark alone
+        // would not take it off
+        let mut history = History::new(puzzle.clone());
+        history.set(cells[1], wrong(cells[1])).unwrap();
+        history.toggle_mark(cells[1], solution.get(cells[1]));
+        assert_eq!(history.first_mistake().unwrap(), Some(1));
+        history.goto(1);
+        history.undo();
+        assert_eq!(history.game().get(cells[1]), Token::None);
+    }
+}
@@ -0,0 +1,143 @@
+use super::{Candidates, Cell, Game, Size, Token};
+
+// Rules on top of the row, column and region uniqueness, such as the ones of Sudoku variants.
+// The solvers, the generator and the uniqueness checks consult every constraint that a game carries
+pub trait Constraint: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Whether the token in the cell keeps the rule, given the rest of the board. The board might
+    // already hold the token in the cell, so the cell itself must not be compared against
+    fn allows(&self, game: &Game, cell: Cell, token: Token) -> bool;
+
+    // Removes the candidates of an empty cell that the rule forbids. Checks each of them with
+    // `allows`, unless the constraint has a faster way
+    fn eliminate(&self, game: &Game, cell: Cell, candidates: Candidates) -> Candidates {
+        let mut kept = candidates;
+        for token in candidates.iter() {
+            if !self.allows(game, cell, token) {
+                kept.remove(token);
+            }
+        }
+        kept
+    }
+
+    // Groups of cells that hold every token once on top of the rows, columns and regions, so that
+    // the solvers can look for hidden singles in them. Rules that do not make whole units have none
+    fn units(&self, _size: Size) -> Vec<Vec<Cell>> {
+        Vec::new()
+    }
+
+    // How hints refer to one of those units, counting from 0
+    fn unit_name(&self, unit: usize) -> String {
+        format!("{} unit {}", self.name(), unit + 1)
+    }
+}
+
+impl std::fmt::Debug for dyn Constraint {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Constraint;
+    use crate::game::{ops, Backend, Cell, Game, Size, Token};
+    use crate::index::BoardIndexer;
+    use rand::SeedableRng;
+
+    // Each token once on the main diagonal
+    struct Diagonal;
+
+    impl Constraint for Diagonal {
+        fn name(&self) -> &'static str {
+            "Diagonal"
+        }
+
+        fn allows(&self, game: &Game, cell: Cell, token: Token) -> bool {
+            cell.row() != cell.column()
+                || (0..game.size().side())
+                    .filter(|index| *index != cell.row())
+                    .all(|index| game.get(Cell::new(index, index)) != token)
+        }
+    }
+
+    fn diagonal() -> Game {
+        Game::new_empty(Size::STANDARD).with_constraint(Diagonal)
+    }
+
+    #[test]
+    fn veto() {
+        let mut game = diagonal();
+        assert!(game.set(Cell::new(0, 0), Token::Five).unwrap());
+        assert!(!game.set(Cell::new(8, 8), Token::Five).unwrap());
+        assert!(game.set(Cell::new(8, 8), Token::Seven).unwrap());
+        assert!(game.set(Cell::new(8, 7), Token::Five).unwrap());
+
+        let candidates = game.candidates(Cell::new(4, 4));
+        assert!(!candidates.contains(Token::Five));
+        assert!(!candidates.contains(Token::Seven));
+        assert_eq!(candidates.len(), 7);
+        assert!(game.candidates(Cell::new(4, 5)).contains(Token::Five));
+    }
+
+    #[test]
+    fn solve() {
+        let mut rng = rand::thread_rng();
+        let solved = diagonal().solve(&mut rng).unwrap();
+        assert!(solved.is_solved());
+        assert_eq!(solved.constraints().len(), 1);
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert!(Diagonal.allows(&solved, cell, solved.get(cell)));
+        }
+
+        // The usual solution breaks the rule
+        let plain = Game::from(ops::consistent_board());
+        assert!(plain.is_solved());
+        let constrained = plain.clone().with_constraint(Diagonal);
+        assert!(!constrained.is_solved());
+        assert_eq!(constrained.count_solutions(2), 0);
+    }
+
+    #[test]
+    fn backends_agree() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(13);
+        let mut puzzle = diagonal().solve(&mut rng).unwrap();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            if (cell.row() + cell.column() * 2) % 5 != 0 {
+                puzzle.set_internal(cell, Token::None);
+            }
+        }
+
+        let backtracking = Backend::Backtracking.solver().solutions(&puzzle, 10);
+        let dancing_links = Backend::DancingLinks.solver().solutions(&puzzle, 10);
+        assert!(!backtracking.is_empty());
+        assert_eq!(backtracking.len(), dancing_links.len());
+        for solution in backtracking.iter().chain(&dancing_links) {
+            assert!(solution.is_solved());
+        }
+    }
+
+    #[test]
+    fn logic() {
+        let mut rng = rand::thread_rng();
+        let solved = diagonal().solve(&mut rng).unwrap();
+        let mut puzzle = solved.clone();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            if cell.row() % 3 != 0 {
+                puzzle.set_internal(cell, Token::None);
+            }
+        }
+
+        // Every step must agree with the only solution that keeps the rule
+        if puzzle.count_solutions(2) == 1 {
+            let trace = puzzle.solve_logically();
+            for cell in BoardIndexer::new(Size::STANDARD) {
+                let token = trace.game.get(cell);
+                if token != Token::None {
+                    assert_eq!(token, solved.get(cell));
+                }
+            }
+        }
+    }
+}
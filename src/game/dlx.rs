@@ -0,0 +1,457 @@
+use super::{ops, Candidates, Cell, Game, Regions, Size, Solver, Token};
+use crate::index::BoardIndexer;
+
+const ROOT: usize = 0;
+
+pub struct DancingLinks;
+
+impl Solver for DancingLinks {
+    fn solutions(&self, game: &Game, limit: usize) -> Vec<Game> {
+        let mut solutions = Vec::new();
+        if limit == 0 {
+            return solutions;
+        }
+
+        if let Some(mut links) = Links::from_game(game) {
+            links.search(limit, &mut 0, &mut |choices| {
+                let mut solved = game.clone();
+                for choice in choices {
+                    let (cell, token) = placement(game.size(), *choice);
+                    solved.set_internal(cell, token);
+                }
+                solutions.push(solved);
+            });
+        }
+
+        solutions
+    }
+
+    fn count_solutions(&self, game: &Game, limit: usize) -> usize {
+        let mut count = 0;
+        if limit == 0 {
+            return count;
+        }
+
+        if let Some(mut links) = Links::from_game(game) {
+            links.search(limit, &mut count, &mut |_| {});
+        }
+        count
+    }
+}
+
+#[inline]
+fn choice(size: Size, cell: Cell, token: Token) -> usize {
+    size.index(cell) * size.side() + token as usize - 1
+}
+
+#[inline]
+fn placement(size: Size, choice: usize) -> (Cell, Token) {
+    (
+        size.cell(choice / size.side()),
+        size.tokens()[choice % size.side()],
+    )
+}
+
+// Exact cover matrix in the toroidal doubly linked representation from Knuth's Algorithm X.
+// Index 0 is the root, followed by the column headers and then four nodes per choice
+struct Links {
+    // Constraints cannot be put in the matrix, so choices are checked against them on a board
+    // that follows the search
+    game: Option<Game>,
+    columns: usize,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    header: Vec<usize>,
+    choice: Vec<usize>,
+    size: Vec<usize>,
+    selected: Vec<usize>,
+}
+
+impl Links {
+    fn new(regions: &Regions) -> Self {
+        let board = regions.size();
+        // One column per constraint: each cell filled once, and each token once per row, column
+        // and sector. One row per possible placement of a token in a cell
+        let cells = board.cells();
+        let columns = 4 * cells;
+        let nodes = 1 + columns + board.side() * cells * 4;
+        let mut links = Self {
+            game: None,
+            columns,
+            left: Vec::with_capacity(nodes),
+            right: Vec::with_capacity(nodes),
+            up: Vec::with_capacity(nodes),
+            down: Vec::with_capacity(nodes),
+            header: Vec::with_capacity(nodes),
+            choice: Vec::with_capacity(nodes),
+            size: vec![0; columns + 1],
+            selected: Vec::with_capacity(cells),
+        };
+
+        for node in 0..=columns {
+            links
+                .left
+                .push(if node == ROOT { columns } else { node - 1 });
+            links
+                .right
+                .push(if node == columns { ROOT } else { node + 1 });
+            links.up.push(node);
+            links.down.push(node);
+            links.header.push(node);
+            links.choice.push(usize::MAX);
+        }
+
+        let side = board.side();
+        for cell in BoardIndexer::new(board) {
+            let row = cell.row();
+            let column = cell.column();
+            let sector = regions.region(cell);
+            for (digit, token) in board.tokens().iter().enumerate() {
+                links.append(
+                    choice(board, cell, *token),
+                    [
+                        1 + board.index(cell),
+                        1 + cells + row * side + digit,
+                        1 + 2 * cells + column * side + digit,
+                        1 + 3 * cells + sector * side + digit,
+                    ],
+                );
+            }
+        }
+
+        links
+    }
+
+    fn from_game(game: &Game) -> Option<Self> {
+        let size = game.size();
+        let mut links = Self::new(game.regions());
+        for cell in BoardIndexer::new(size) {
+            let token = game.get(cell);
+            if token != Token::None && !links.select(choice(size, cell, token)) {
+                return None;
+            }
+        }
+
+        if !game.constraints().is_empty() {
+            if !ops::valid(game) {
+                return None;
+            }
+            links.game = Some(game.clone());
+            links.eliminate();
+        }
+        Some(links)
+    }
+
+    fn append(&mut self, choice: usize, columns: [usize; 4]) {
+        let first = self.left.len();
+        for (index, column) in columns.iter().enumerate() {
+            let node = first + index;
+            self.left
+                .push(if index == 0 { first + 3 } else { node - 1 });
+            self.right.push(if index == 3 { first } else { node + 1 });
+            self.up.push(self.up[*column]);
+            self.down.push(*column);
+            self.header.push(*column);
+            self.choice.push(choice);
+
+            let last = self.up[*column];
+            self.down[last] = node;
+            self.up[*column] = node;
+            self.size[*column] += 1;
+        }
+    }
+
+    // Forces a choice into the solution. Returns false if it clashes with a previous selection
+    fn select(&mut self, choice: usize) -> bool {
+        let first = 1 + self.columns + choice * 4;
+        let mut node = first;
+        loop {
+            let column = self.header[node];
+            if self.right[self.left[column]] != column {
+                return false;
+            }
+            node = self.right[node];
+            if node == first {
+                break;
+            }
+        }
+
+        loop {
+            self.cover(self.header[node]);
+            node = self.right[node];
+            if node == first {
+                break;
+            }
+        }
+        true
+    }
+
+    fn search(&mut self, limit: usize, count: &mut usize, visit: &mut dyn FnMut(&[usize])) {
+        if self.right[ROOT] == ROOT {
+            *count += 1;
+            visit(&self.selected);
+            return;
+        }
+
+        let column = self.smallest_column();
+        if self.size[column] == 0 {
+            return;
+        }
+
+        self.cover(column);
+
+        let mut row = self.down[column];
+        while row != column {
+            if !self.allows(self.choice[row]) {
+                row = self.down[row];
+                continue;
+            }
+            self.selected.push(self.choice[row]);
+
+            let mut node = self.right[row];
+            while node != row {
+                self.cover(self.header[node]);
+                node = self.right[node];
+            }
+
+            let hidden = self.eliminate();
+            self.search(limit, count, visit);
+            for row in hidden.into_iter().rev() {
+                self.unhide(row);
+            }
+
+            node = self.left[row];
+            while node != row {
+                self.uncover(self.header[node]);
+                node = self.left[node];
+            }
+
+            self.selected.pop();
+            self.retract(self.choice[row]);
+            if *count >= limit {
+                break;
+            }
+            row = self.down[row];
+        }
+
+        self.uncover(column);
+    }
+
+    // Places the choice on the board that follows the search if the constraints allow it. They are
+    // asked to eliminate before the placement, which can look further than whether they allow it
+    fn allows(&mut self, choice: usize) -> bool {
+        let Some(game) = &mut self.game else {
+            return true;
+        };
+
+        let (cell, token) = placement(game.size(), choice);
+        let mut candidates = Candidates::empty();
+        candidates.insert(token);
+        let allowed = game
+            .constraints()
+            .iter()
+            .all(|constraint| constraint.eliminate(game, cell, candidates).contains(token));
+        if allowed {
+            game.set_internal(cell, token);
+        }
+        allowed
+    }
+
+    // Hides the choices that the constraints rule out on the board that follows the search, so that
+    // the column sizes account for them. Returns the first node of each hidden choice, to unhide
+    // them in reverse order
+    fn eliminate(&mut self) -> Vec<usize> {
+        let mut hidden = Vec::new();
+        let Some(game) = self.game.take() else {
+            return hidden;
+        };
+
+        let size = game.size();
+        for cell in BoardIndexer::new(size) {
+            if game.get(cell) != Token::None {
+                continue;
+            }
+
+            let candidates = game.candidates(cell);
+            for token in size.tokens() {
+                let first = 1 + self.columns + choice(size, cell, *token) * 4;
+                if !candidates.contains(*token) && self.active(first) {
+                    self.hide(first);
+                    hidden.push(first);
+                }
+            }
+        }
+
+        self.game = Some(game);
+        hidden
+    }
+
+    // Whether the choice is still in the matrix, which it is unless one of its columns is covered
+    // or it was hidden
+    fn active(&self, first: usize) -> bool {
+        let mut node = first;
+        loop {
+            let column = self.header[node];
+            if self.right[self.left[column]] != column || self.down[self.up[node]] != node {
+                return false;
+            }
+            node = self.right[node];
+            if node == first {
+                return true;
+            }
+        }
+    }
+
+    fn hide(&mut self, first: usize) {
+        let mut node = first;
+        loop {
+            self.down[self.up[node]] = self.down[node];
+            self.up[self.down[node]] = self.up[node];
+            self.size[self.header[node]] -= 1;
+            node = self.right[node];
+            if node == first {
+                break;
+            }
+        }
+    }
+
+    fn unhide(&mut self, first: usize) {
+        let mut node = self.left[first];
+        loop {
+            self.size[self.header[node]] += 1;
+            self.down[self.up[node]] = node;
+            self.up[self.down[node]] = node;
+            if node == first {
+                break;
+            }
+            node = self.left[node];
+        }
+    }
+
+    fn retract(&mut self, choice: usize) {
+        if let Some(game) = &mut self.game {
+            let (cell, _) = placement(game.size(), choice);
+            game.set_internal(cell, Token::None);
+        }
+    }
+
+    fn smallest_column(&self) -> usize {
+        let mut smallest = self.right[ROOT];
+        let mut column = self.right[smallest];
+        while column != ROOT && self.size[smallest] > 1 {
+            if self.size[column] < self.size[smallest] {
+                smallest = column;
+            }
+            column = self.right[column];
+        }
+        smallest
+    }
+
+    fn cover(&mut self, column: usize) {
+        self.right[self.left[column]] = self.right[column];
+        self.left[self.right[column]] = self.left[column];
+
+        let mut row = self.down[column];
+        while row != column {
+            let mut node = self.right[row];
+            while node != row {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.header[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    fn uncover(&mut self, column: usize) {
+        let mut row = self.up[column];
+        while row != column {
+            let mut node = self.left[row];
+            while node != row {
+                self.size[self.header[node]] += 1;
+                self.down[self.up[node]] = node;
+                self.up[self.down[node]] = node;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+
+        self.right[self.left[column]] = column;
+        self.left[self.right[column]] = column;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DancingLinks, Solver};
+    use crate::game::{ops, Cell, Game, Size, Token};
+    use crate::index::BoardIndexer;
+
+    #[test]
+    fn choice_placement() {
+        for size in &[Size::STANDARD, Size::new(2, 3).unwrap()] {
+            for cell in BoardIndexer::new(*size) {
+                for token in size.tokens() {
+                    assert_eq!(
+                        super::placement(*size, super::choice(*size, cell, *token)),
+                        (cell, *token)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve_hard() {
+        let game = Game::from(ops::hard_board());
+        let solutions = DancingLinks.solutions(&game, 2);
+        assert_eq!(solutions.len(), 1);
+
+        let expected = &ops::solutions(&game, 1)[0];
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(solutions[0].get(cell), expected.get(cell));
+        }
+    }
+
+    #[test]
+    fn solved_board() {
+        let game = Game::from(ops::consistent_board());
+        let solutions = DancingLinks.solutions(&game, 2);
+        assert_eq!(solutions.len(), 1);
+        assert!(ops::solved(&solutions[0]));
+    }
+
+    #[test]
+    fn count_solutions() {
+        let empty = Game::new_empty(Size::STANDARD);
+        assert_eq!(DancingLinks.count_solutions(&empty, 0), 0);
+        assert_eq!(DancingLinks.count_solutions(&empty, 1), 1);
+        assert_eq!(DancingLinks.count_solutions(&empty, 25), 25);
+
+        for solution in DancingLinks.solutions(&empty, 5) {
+            assert!(ops::solved(&solution));
+        }
+    }
+
+    #[test]
+    fn clashing_givens() {
+        let mut game = Game::new_empty(Size::STANDARD);
+        game.set_internal(Cell::new(0, 0), Token::Four);
+        game.set_internal(Cell::new(8, 0), Token::Four);
+        assert_eq!(DancingLinks.count_solutions(&game, 2), 0);
+        assert!(DancingLinks.solutions(&game, 2).is_empty());
+    }
+
+    #[test]
+    fn large() {
+        let size = Size::new(4, 4).unwrap();
+        let solutions = DancingLinks.solutions(&Game::new_empty(size), 2);
+        assert_eq!(solutions.len(), 2);
+        for solution in &solutions {
+            assert!(ops::solved(solution));
+        }
+    }
+}
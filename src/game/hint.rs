@@ -0,0 +1,412 @@
+use super::logic::{self, Step, Technique};
+use super::{ops, Cell, Game, Token};
+
+// How much a hint gives away, from where to look to the whole deduction
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Tier {
+    Nudge,
+    Technique,
+    Answer,
+}
+
+impl Tier {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Nudge => "Nudge",
+            Tier::Technique => "Technique",
+            Tier::Answer => "Answer",
+        }
+    }
+}
+
+impl std::fmt::Display for Tier {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+// The easiest deduction left on the board, along with the words to explain it
+#[derive(Clone, Debug)]
+pub struct Hint {
+    step: Step,
+    place: String,
+    explanation: String,
+}
+
+impl Hint {
+    #[inline]
+    pub fn technique(&self) -> Technique {
+        self.step.technique
+    }
+
+    // The cells that make up the pattern
+    #[inline]
+    pub fn cells(&self) -> &[Cell] {
+        &self.step.cells
+    }
+
+    #[inline]
+    pub fn eliminations(&self) -> &[(Cell, Token)] {
+        &self.step.eliminations
+    }
+
+    #[inline]
+    pub fn placements(&self) -> &[(Cell, Token)] {
+        &self.step.placements
+    }
+
+    pub fn text(&self, tier: Tier) -> String {
+        let technique = self.technique();
+        match tier {
+            Tier::Nudge => format!("Look at {}", self.place),
+            Tier::Technique => format!(
+                "Look for {}{technique} in {}",
+                article(technique),
+                self.place
+            ),
+            Tier::Answer => self.explanation.clone(),
+        }
+    }
+}
+
+pub fn next(game: &Game) -> Option<Hint> {
+    if !ops::valid(game) {
+        return None;
+    }
+
+    let step = logic::next(game)?;
+    let side = game.size().side();
+    let unit = step.unit.map(|unit| unit_name(game, unit));
+    let cells = list(step.cells.iter());
+    let first = step.cells[0];
+    let token = step
+        .placements
+        .iter()
+        .chain(&step.eliminations)
+        .map(|(_, token)| *token)
+        .next()
+        .unwrap_or(Token::None);
+    let result = result(&step);
+
+    let (place, explanation) = match step.technique {
+        Technique::NakedSingle => (
+            unit_name(game, 2 * side + game.regions().region(first)),
+            format!("{first} has no candidates left other than {token}, {result}"),
+        ),
+        Technique::HiddenSingle => {
+            let unit = unit.unwrap_or_default();
+            let explanation =
+                format!("{first} is the only place left for {token} in {unit}, {result}");
+            (unit, explanation)
+        }
+        Technique::Pointing | Technique::Claiming => {
+            let unit = unit.unwrap_or_default();
+            let other = if step.technique == Technique::Claiming {
+                unit_name(game, 2 * side + game.regions().region(first))
+            } else if step.cells.iter().all(|cell| cell.row() == first.row()) {
+                unit_name(game, first.row())
+            } else {
+                unit_name(game, side + first.column())
+            };
+            let explanation = format!(
+                "In {unit}, {token} can only go in {cells}, which all lie in {other}, {result}"
+            );
+            (unit, explanation)
+        }
+        Technique::NakedPair
+        | Technique::NakedTriple
+        | Technique::NakedQuad
+        | Technique::HiddenPair
+        | Technique::HiddenTriple
+        | Technique::HiddenQuad => {
+            let unit = unit.unwrap_or_default();
+            let explanation = subset(game, &step, &unit, &cells, &result);
+            (unit, explanation)
+        }
+        Technique::XWing | Technique::Swordfish | Technique::Jellyfish => {
+            fish(&step, token, &result)
+        }
+        Technique::XyWing | Technique::XyzWing => {
+            let (pivot, first, second) = (step.cells[0], step.cells[1], step.cells[2]);
+            let tokens = list(game.candidates(pivot).iter());
+            let explanation = if step.technique == Technique::XyWing {
+                format!(
+                    "{pivot} is either {}. Either way one of {first} and {second} is {token}, \
+                     {result}",
+                    tokens.replace(" and ", " or ")
+                )
+            } else {
+                format!(
+                    "{pivot} can only be {tokens}, and {first} and {second} share {token} with \
+                     it and cover the rest. One of the three is {token}, {result}"
+                )
+            };
+            (format!("{pivot} and the cells it sees"), explanation)
+        }
+        Technique::SimpleColoring => (
+            format!("the {token}s across the board"),
+            format!(
+                "{cells} are linked by units where {token} has only two places, so they \
+                 alternate between holding {token} and not, {result}"
+            ),
+        ),
+        Technique::XChain => {
+            let (start, end) = (step.cells[0], step.cells[1]);
+            (
+                format!("the {token}s across the board"),
+                format!(
+                    "A chain of {token}s that alternates between units where it has only two \
+                     places and cells that see each other links {start} and {end}. One of them \
+                     is {token}, {result}"
+                ),
+            )
+        }
+    };
+
+    Some(Hint {
+        step,
+        place,
+        explanation,
+    })
+}
+
+// Naked subsets hold their tokens between their cells, and hidden ones are the only place for them
+fn subset(game: &Game, step: &Step, unit: &str, cells: &str, result: &str) -> String {
+    let removed = step
+        .eliminations
+        .iter()
+        .map(|(_, token)| *token)
+        .collect::<Vec<_>>();
+    let naked = matches!(
+        step.technique,
+        Technique::NakedPair | Technique::NakedTriple | Technique::NakedQuad
+    );
+    let mut tokens = step
+        .cells
+        .iter()
+        .flat_map(|cell| game.candidates(*cell).iter())
+        .filter(|token| naked || !removed.contains(token))
+        .collect::<Vec<_>>();
+    tokens.sort();
+    tokens.dedup();
+    let tokens = list(tokens.iter());
+
+    if naked {
+        format!(
+            "In {unit}, {cells} can only hold {tokens} between them, which keeps them out of the \
+             rest of the unit, {result}"
+        )
+    } else {
+        format!(
+            "In {unit}, {tokens} can only go in {cells}, which leaves no room for anything else \
+             there, {result}"
+        )
+    }
+}
+
+// The lines the token is confined in are the ones the eliminations are not in. Returns where to
+// look and the explanation
+fn fish(step: &Step, token: Token, result: &str) -> (String, String) {
+    let rows = sorted(step.cells.iter().map(|cell| cell.row() + 1).collect());
+    let columns = sorted(step.cells.iter().map(|cell| cell.column() + 1).collect());
+    let across = step
+        .eliminations
+        .iter()
+        .all(|(cell, _)| !rows.contains(&(cell.row() + 1)));
+    let ((base, lines), (cover, covered)) = if across {
+        (("rows", rows), ("columns", columns))
+    } else {
+        (("columns", columns), ("rows", rows))
+    };
+
+    let lines = format!("{base} {}", list(lines.iter()));
+    let explanation = format!(
+        "In {lines}, {token} can only go in {cover} {}, so each of those {cover} takes its \
+         {token} from one of these {base}, {result}",
+        list(covered.iter()),
+    );
+    (format!("the {token}s in {lines}"), explanation)
+}
+
+// Techniques named after what the token does go without one
+fn article(technique: Technique) -> &'static str {
+    match technique {
+        Technique::Pointing | Technique::Claiming | Technique::SimpleColoring => "",
+        Technique::XWing | Technique::XyWing | Technique::XyzWing | Technique::XChain => "an ",
+        _ => "a ",
+    }
+}
+
+// Units are numbered the way the logic solver lays them out: rows, columns, regions and then the
+// ones added by the constraints
+fn unit_name(game: &Game, mut unit: usize) -> String {
+    let side = game.size().side();
+    if unit < side {
+        return format!("row {}", unit + 1);
+    }
+    if unit < 2 * side {
+        return format!("column {}", unit - side + 1);
+    }
+    if unit < 3 * side {
+        let kind = if game.regions().is_standard() {
+            "box"
+        } else {
+            "region"
+        };
+        return format!("{kind} {}", unit - 2 * side + 1);
+    }
+
+    unit -= 3 * side;
+    for constraint in game.constraints() {
+        let count = constraint.units(game.size()).len();
+        if unit < count {
+            return constraint.unit_name(unit);
+        }
+        unit -= count;
+    }
+    String::new()
+}
+
+// What the step does to the board, finishing off the explanation
+fn result(step: &Step) -> String {
+    if let Some((cell, token)) = step.placements.first() {
+        return format!("so {cell} must be {token}");
+    }
+
+    let mut tokens = step
+        .eliminations
+        .iter()
+        .map(|(_, token)| *token)
+        .collect::<Vec<_>>();
+    tokens.sort();
+    tokens.dedup();
+    let removals = tokens.iter().map(|token| {
+        let cells = step
+            .eliminations
+            .iter()
+            .filter(|(_, other)| other == token)
+            .map(|(cell, _)| cell);
+        format!("{token} from {}", list(cells))
+    });
+    let removals = removals.collect::<Vec<_>>();
+    match removals.split_last() {
+        Some((last, rest)) if !rest.is_empty() => {
+            format!("so this removes {}, and {last}", rest.join(", "))
+        }
+        _ => format!("so this removes {}", removals.concat()),
+    }
+}
+
+fn sorted<T: Ord>(mut items: Vec<T>) -> Vec<T> {
+    items.sort();
+    items.dedup();
+    items
+}
+
+// Items as in "a, b and c"
+fn list<T: std::fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    let items = items.map(|item| item.to_string()).collect::<Vec<_>>();
+    match items.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tier;
+    use crate::game::logic::Technique;
+    use crate::game::{ops, Cell, Game, Regions, Size, Token, Variant};
+
+    #[test]
+    fn tiers() {
+        #[rustfmt::skip]
+        let game = Game::from([
+            1, 0, 0, 0, 0, 0, 7, 0, 9,
+            0, 4, 0, 0, 0, 7, 2, 0, 0,
+            8, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 7, 0, 0, 1, 0, 0, 6, 0,
+            3, 0, 0, 0, 0, 0, 0, 0, 5,
+            0, 6, 0, 0, 4, 0, 0, 2, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 8,
+            0, 0, 5, 3, 0, 0, 0, 7, 0,
+            7, 0, 2, 0, 0, 0, 0, 4, 6,
+        ]);
+        let hint = game.next_hint().unwrap();
+        assert_eq!(hint.technique(), Technique::HiddenSingle);
+        assert_eq!(hint.cells(), &[Cell::new(2, 2)]);
+        assert_eq!(hint.placements(), &[(Cell::new(2, 2), Token::Seven)]);
+        assert!(hint.eliminations().is_empty());
+
+        assert_eq!(hint.text(Tier::Nudge), "Look at row 3");
+        assert_eq!(
+            hint.text(Tier::Technique),
+            "Look for a Hidden Single in row 3"
+        );
+        assert_eq!(
+            hint.text(Tier::Answer),
+            "r3c3 is the only place left for 7 in row 3, so r3c3 must be 7"
+        );
+
+        // A deduction from a clash would only spread the mistake
+        let mut clashing = game.clone();
+        clashing.set_internal(Cell::new(0, 1), Token::One);
+        assert!(clashing.next_hint().is_none());
+        assert_eq!(clashing.clashes(), &[Cell::new(0, 0), Cell::new(0, 1)]);
+    }
+
+    #[test]
+    fn eliminations() {
+        #[rustfmt::skip]
+        let game = Game::from([
+            0, 0, 0, 0, 0, 6, 0, 0, 0,
+            0, 5, 9, 0, 0, 0, 0, 0, 8,
+            2, 0, 0, 0, 0, 8, 0, 0, 0,
+            0, 4, 5, 0, 0, 0, 0, 0, 0,
+            0, 0, 3, 0, 0, 0, 0, 0, 0,
+            0, 0, 6, 0, 0, 3, 0, 5, 4,
+            0, 0, 0, 3, 2, 5, 0, 0, 6,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        let hint = game.next_hint().unwrap();
+        assert_eq!(hint.technique(), Technique::Pointing);
+        assert_eq!(hint.cells(), &[Cell::new(4, 1), Cell::new(5, 1)]);
+        assert_eq!(hint.text(Tier::Technique), "Look for Pointing in box 4");
+        assert_eq!(
+            hint.text(Tier::Answer),
+            "In box 4, 2 can only go in r5c2 and r6c2, which all lie in column 2, so this removes \
+             2 from r8c2 and r9c2"
+        );
+    }
+
+    #[test]
+    fn none() {
+        assert!(Game::from(ops::consistent_board()).next_hint().is_none());
+        assert!(Game::from(ops::hard_board()).next_hint().is_none());
+    }
+
+    #[test]
+    fn unit_name() {
+        let game = Game::new_empty(Size::STANDARD)
+            .with_variant(Variant::Diagonal)
+            .with_variant(Variant::Windoku);
+        assert_eq!(super::unit_name(&game, 4), "row 5");
+        assert_eq!(super::unit_name(&game, 13), "column 5");
+        assert_eq!(super::unit_name(&game, 22), "box 5");
+        assert_eq!(super::unit_name(&game, 28), "the anti-diagonal");
+        assert_eq!(super::unit_name(&game, 30), "window 2");
+
+        let regions = Regions::new(Size::STANDARD, &crate::game::jigsaw_labels()).unwrap();
+        let game = Game::with_regions(regions, vec![Token::None; 81]);
+        assert_eq!(super::unit_name(&game, 18), "region 1");
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(super::list(["a"].iter()), "a");
+        assert_eq!(super::list(["a", "b"].iter()), "a and b");
+        assert_eq!(super::list(["a", "b", "c"].iter()), "a, b and c");
+    }
+}
@@ -0,0 +1,324 @@
+use super::{check, Candidates, Cell, Check, Game, Token};
+use crate::error::Error;
+
+// A change made by the player: the token of a cell and the pencil marks it touched, before and
+// after. A move that only edits pencil marks keeps the token of its cell
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Move {
+    cell: Cell,
+    old: Token,
+    new: Token,
+    marks: Vec<(Cell, Candidates, Candidates)>,
+}
+
+impl Move {
+    #[inline]
+    pub fn cell(&self) -> Cell {
+        self.cell
+    }
+
+    #[inline]
+    pub fn old(&self) -> Token {
+        self.old
+    }
+
+    // The token of the cell after the move
+    #[inline]
+    pub fn token(&self) -> Token {
+        self.new
+    }
+
+    // Each cell whose marks changed, with its marks before and after the move
+    #[inline]
+    pub fn marks(&self) -> &[(Cell, Candidates, Candidates)] {
+        &self.marks
+    }
+}
+
+// Moves made on a game, kept as a tree: a move made after undoing some starts a new branch instead
+// of dropping the undone ones, so every point of the attempt can be gone back to. Points are
+// numbered by the move that leads to them, starting from 1, and 0 is the game as it was given
+pub struct History {
+    game: Game,
+    moves: Vec<Move>,
+    parents: Vec<usize>,
+    point: usize,
+}
+
+impl History {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game,
+            moves: Vec::new(),
+            parents: Vec::new(),
+            point: 0,
+        }
+    }
+
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Every move recorded, across all branches, in the order they were made
+    #[inline]
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    #[inline]
+    pub fn point(&self) -> usize {
+        self.point
+    }
+
+    // Moves that lead from the given game to the current point, to replay the attempt
+    pub fn line(&self) -> Vec<&Move> {
+        let mut line = self.path(self.point);
+        line.reverse();
+        line.into_iter()
+            .map(|point| &self.moves[point - 1])
+            .collect()
+    }
+
+    // The tokens of the game as it was given, before the moves
+    pub fn start(&self) -> Game {
+        let mut game = self.game.clone();
+        for change in self.line().into_iter().rev() {
+            game.set_internal(change.cell, change.old);
+        }
+        game
+    }
+
+    pub fn check(&self) -> Result<Check, Error> {
+        check::check(&self.start(), &self.game)
+    }
+
+    // The point of the move that put the earliest wrong token still on the board, to go back to
+    // the one before it. Marks toggled on the cell afterwards do not count, since they leave the
+    // token as it is
+    pub fn first_mistake(&self) -> Result<Option<usize>, Error> {
+        let check = self.check()?;
+        let line = self.path(self.point);
+        Ok(check
+            .wrong()
+            .iter()
+            .filter_map(|cell| {
+                line.iter().copied().find(|point| {
+                    let played = &self.moves[point - 1];
+                    played.cell == *cell && played.old != played.new
+                })
+            })
+            .min())
+    }
+
+    // Same as `Game::set`, and recorded unless the cell is a given or already holds the token
+    pub fn set(&mut self, cell: Cell, token: Token) -> Result<bool, Error> {
+        let old = self.game.get(cell);
+        let before = self.game.marks.clone();
+        let consistent = self.game.set(cell, token)?;
+        if old != token {
+            self.record(cell, old, &before);
+        }
+        Ok(consistent)
+    }
+
+    // Same as `Game::toggle_mark`, and always recorded
+    pub fn toggle_mark(&mut self, cell: Cell, token: Token) -> bool {
+        let before = self.game.marks.clone();
+        let marked = self.game.toggle_mark(cell, token);
+        self.record(cell, self.game.get(cell), &before);
+        marked
+    }
+
+    // Returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        if self.point == 0 {
+            return false;
+        }
+        let parent = self.parents[self.point - 1];
+        self.goto(parent);
+        true
+    }
+
+    // Makes the latest move out of the current point again. Returns false if there is none
+    pub fn redo(&mut self) -> bool {
+        match (self.point..self.moves.len())
+            .rev()
+            .find(|index| self.parents[*index] == self.point)
+        {
+            Some(index) => {
+                self.goto(index + 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Undoes moves up to the closest point that both share, then makes the moves down to the other
+    // one. Returns false if there is no such point yet
+    pub fn goto(&mut self, point: usize) -> bool {
+        if point > self.moves.len() {
+            return false;
+        }
+
+        let shared = self.shared(self.point, point);
+        while self.point != shared {
+            let change = self.moves[self.point - 1].clone();
+            let marks = change.marks.iter().map(|(cell, old, _)| (*cell, *old));
+            self.apply(change.cell, change.old, marks);
+            self.point = self.parents[self.point - 1];
+        }
+
+        let down = self
+            .path(point)
+            .into_iter()
+            .take_while(|other| *other != shared)
+            .collect::<Vec<_>>();
+        for other in down.into_iter().rev() {
+            let change = self.moves[other - 1].clone();
+            let marks = change.marks.iter().map(|(cell, _, new)| (*cell, *new));
+            self.apply(change.cell, change.new, marks);
+        }
+        self.point = point;
+        true
+    }
+
+    fn record(&mut self, cell: Cell, old: Token, before: &[Candidates]) {
+        let size = self.game.size();
+        let after = &self.game.marks;
+        let marks = (0..std::cmp::max(before.len(), after.len()))
+            .map(|index| {
+                let mark = |marks: &[Candidates]| {
+                    marks.get(index).copied().unwrap_or_else(Candidates::empty)
+                };
+                (size.cell(index), mark(before), mark(after))
+            })
+            .filter(|(_, before, after)| before != after)
+            .collect();
+
+        self.moves.push(Move {
+            cell,
+            old,
+            new: self.game.get(cell),
+            marks,
+        });
+        self.parents.push(self.point);
+        self.point = self.moves.len();
+    }
+
+    // Puts the token and the marks back without the checks of `Game::set`, since the givens are
+    // never part of a move and the marks are restored as they were
+    fn apply(&mut self, cell: Cell, token: Token, marks: impl Iterator<Item = (Cell, Candidates)>) {
+        self.game.set_internal(cell, token);
+        for (cell, candidates) in marks {
+            if self.game.marks.is_empty() {
+                self.game.marks = vec![Candidates::empty(); self.game.size().cells()];
+            }
+            let index = self.game.size().index(cell);
+            self.game.marks[index] = candidates;
+        }
+    }
+
+    // Points from the given one back to the start, not counting the start
+    fn path(&self, mut point: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        while point != 0 {
+            path.push(point);
+            point = self.parents[point - 1];
+        }
+        path
+    }
+
+    fn shared(&self, first: usize, second: usize) -> usize {
+        let second = self.path(second);
+        self.path(first)
+            .into_iter()
+            .find(|point| second.contains(point))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::game::{ops, Cell, Game, Token};
+
+    #[test]
+    fn undo_redo() {
+        let puzzle = Game::from(ops::hard_board());
+        let mut history = History::new(puzzle.clone());
+        let (first, second) = (Cell::new(0, 1), Cell::new(0, 2));
+        assert!(!history.undo());
+        assert!(history.set(Cell::new(0, 0), Token::Two).is_err());
+        assert!(history.moves().is_empty());
+
+        history.set(first, Token::Two).unwrap();
+        history.set(first, Token::Four).unwrap();
+        history.set(second, Token::Six).unwrap();
+        assert_eq!(history.point(), 3);
+        assert_eq!(history.moves()[1].old(), Token::Two);
+        assert_eq!(history.moves()[1].token(), Token::Four);
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(history.game().get(first), Token::Two);
+        assert_eq!(history.game().get(second), Token::None);
+        assert!(history.redo());
+        assert!(history.redo());
+        assert!(!history.redo());
+        assert_eq!(history.game().get(second), Token::Six);
+
+        assert!(history.goto(0));
+        assert_eq!(history.game().to_string(), puzzle.to_string());
+        assert!(history.line().is_empty());
+        assert!(!history.goto(4));
+    }
+
+    #[test]
+    fn branches() {
+        let mut history = History::new(Game::from(ops::hard_board()));
+        let (first, second) = (Cell::new(0, 1), Cell::new(0, 2));
+        history.set(first, Token::Two).unwrap();
+        history.set(second, Token::Six).unwrap();
+
+        // Moving after an undo branches off, and redo follows the new branch
+        history.undo();
+        history.set(second, Token::Seven).unwrap();
+        history.undo();
+        assert!(history.redo());
+        assert_eq!(history.point(), 3);
+        assert_eq!(history.game().get(second), Token::Seven);
+
+        // The old branch is still there
+        assert!(history.goto(2));
+        assert_eq!(history.game().get(second), Token::Six);
+        assert_eq!(
+            history
+                .line()
+                .iter()
+                .map(|change| (change.cell(), change.token()))
+                .collect::<Vec<_>>(),
+            vec![(first, Token::Two), (second, Token::Six)]
+        );
+    }
+
+    #[test]
+    fn marks() {
+        let mut history = History::new(Game::from(ops::hard_board()));
+        let (cell, peer) = (Cell::new(0, 1), Cell::new(0, 2));
+        assert!(history.toggle_mark(peer, Token::Two));
+        assert!(history.toggle_mark(peer, Token::Four));
+
+        // Placing a token prunes the marks of its peers, and undoing it puts them back
+        history.set(cell, Token::Two).unwrap();
+        assert_eq!(history.moves()[2].marks().len(), 1);
+        assert!(!history.game().marks(peer).contains(Token::Two));
+        history.undo();
+        assert_eq!(history.game().marks(peer).len(), 2);
+
+        history.goto(1);
+        assert_eq!(history.game().marks(peer).len(), 1);
+        history.goto(0);
+        assert!(history.game().marks(peer).is_empty());
+    }
+}
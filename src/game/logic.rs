@@ -0,0 +1,1174 @@
+use super::{ops, Candidates, Cell, Difficulty, Game, Regions, Size, Token};
+use crate::index::{BoardIndexer, ColumnIndexer, RowIndexer};
+
+// Rows, then columns, then sectors, then the units added by constraints
+type Units = Vec<Vec<Cell>>;
+type Finder = fn(&Grid) -> Option<Step>;
+
+// Ordered from the easiest to the hardest, which is also the order in which they are attempted
+const FINDERS: [Finder; 17] = [
+    naked_single,
+    hidden_single,
+    pointing,
+    claiming,
+    naked_pair,
+    hidden_pair,
+    naked_triple,
+    hidden_triple,
+    naked_quad,
+    hidden_quad,
+    x_wing,
+    swordfish,
+    jellyfish,
+    xy_wing,
+    xyz_wing,
+    simple_coloring,
+    x_chain,
+];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    Pointing,
+    Claiming,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    HiddenQuad,
+    XWing,
+    Swordfish,
+    Jellyfish,
+    XyWing,
+    XyzWing,
+    SimpleColoring,
+    XChain,
+}
+
+impl Technique {
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::Pointing => "Pointing",
+            Technique::Claiming => "Claiming",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::NakedQuad => "Naked Quad",
+            Technique::HiddenQuad => "Hidden Quad",
+            Technique::XWing => "X-Wing",
+            Technique::Swordfish => "Swordfish",
+            Technique::Jellyfish => "Jellyfish",
+            Technique::XyWing => "XY-Wing",
+            Technique::XyzWing => "XYZ-Wing",
+            Technique::SimpleColoring => "Simple Coloring",
+            Technique::XChain => "X-Chain",
+        }
+    }
+
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Beginner,
+            Technique::Pointing | Technique::Claiming => Difficulty::Easy,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::NakedTriple
+            | Technique::HiddenTriple => Difficulty::Medium,
+            Technique::NakedQuad
+            | Technique::HiddenQuad
+            | Technique::XWing
+            | Technique::XyWing
+            | Technique::XyzWing => Difficulty::Hard,
+            Technique::Swordfish
+            | Technique::Jellyfish
+            | Technique::SimpleColoring
+            | Technique::XChain => Difficulty::Expert,
+        }
+    }
+}
+
+impl std::fmt::Display for Technique {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+// The cells of a step are the ones that make up the pattern, and its unit is the index of the one
+// the pattern was found in, for the techniques that work inside of a unit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub technique: Technique,
+    pub cells: Vec<Cell>,
+    pub unit: Option<usize>,
+    pub eliminations: Vec<(Cell, Token)>,
+    pub placements: Vec<(Cell, Token)>,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}:", self.technique)?;
+        for (cell, token) in &self.placements {
+            write!(fmt, " {cell}={token}")?;
+        }
+        for (cell, token) in &self.eliminations {
+            write!(fmt, " {cell}<>{token}")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub game: Game,
+}
+
+impl Trace {
+    pub fn is_solved(&self) -> bool {
+        self.game.is_solved()
+    }
+
+    pub fn hardest(&self) -> Option<Technique> {
+        self.steps.iter().map(|step| step.technique).max()
+    }
+
+    // Puzzles that cannot be finished with the known techniques need guessing or harder logic
+    pub fn difficulty(&self) -> Difficulty {
+        if self.is_solved() {
+            self.steps
+                .iter()
+                .map(|step| step.technique.difficulty())
+                .max()
+                .unwrap_or(Difficulty::Beginner)
+        } else {
+            Difficulty::Diabolical
+        }
+    }
+}
+
+pub fn solve(game: &Game) -> Trace {
+    let mut grid = Grid::new(game);
+    let mut steps = Vec::new();
+
+    while grid.is_open() {
+        let Some(step) = next_step(&grid) else {
+            break;
+        };
+        grid.apply(&step);
+        steps.push(step);
+    }
+
+    Trace {
+        steps,
+        game: grid.game,
+    }
+}
+
+// The easiest step that applies to the board as it is, unless it is full or stuck
+pub fn next(game: &Game) -> Option<Step> {
+    let grid = Grid::new(game);
+    if grid.is_open() {
+        next_step(&grid)
+    } else {
+        None
+    }
+}
+
+fn next_step(grid: &Grid) -> Option<Step> {
+    FINDERS.iter().find_map(|finder| finder(grid))
+}
+
+struct Grid {
+    game: Game,
+    size: Size,
+    regions: Regions,
+    candidates: Vec<Candidates>,
+    units: Units,
+}
+
+impl Grid {
+    fn new(game: &Game) -> Self {
+        let size = game.size();
+        let candidates = BoardIndexer::new(size)
+            .map(|cell| game.candidates(cell))
+            .collect();
+
+        Self {
+            game: game.clone(),
+            size,
+            regions: game.regions().clone(),
+            candidates,
+            units: units(game),
+        }
+    }
+
+    #[inline]
+    fn candidates(&self, cell: Cell) -> Candidates {
+        self.candidates[self.size.index(cell)]
+    }
+
+    #[inline]
+    fn tokens(&self) -> &'static [Token] {
+        self.size.tokens()
+    }
+
+    fn cells(&self) -> BoardIndexer {
+        BoardIndexer::new(self.size)
+    }
+
+    fn rows(&self) -> &[Vec<Cell>] {
+        &self.units[..self.size.side()]
+    }
+
+    fn columns(&self) -> &[Vec<Cell>] {
+        let side = self.size.side();
+        &self.units[side..2 * side]
+    }
+
+    fn sectors(&self) -> &[Vec<Cell>] {
+        let side = self.size.side();
+        &self.units[2 * side..3 * side]
+    }
+
+    // Units added by the constraints, such as diagonals
+    fn extra(&self) -> &[Vec<Cell>] {
+        &self.units[3 * self.size.side()..]
+    }
+
+    #[inline]
+    fn sees(&self, first: Cell, second: Cell) -> bool {
+        first != second
+            && (first.row() == second.row()
+                || first.column() == second.column()
+                || self.regions.region(first) == self.regions.region(second)
+                || self
+                    .extra()
+                    .iter()
+                    .any(|unit| unit.contains(&first) && unit.contains(&second)))
+    }
+
+    fn peers(&self, cell: Cell) -> impl Iterator<Item = Cell> + '_ {
+        self.cells().filter(move |other| self.sees(cell, *other))
+    }
+
+    // Whether there are still empty cells and all of them have something that can be placed
+    fn is_open(&self) -> bool {
+        let mut open = false;
+        for cell in self.cells() {
+            if self.game.get(cell) == Token::None {
+                if self.candidates(cell).is_empty() {
+                    return false;
+                }
+                open = true;
+            }
+        }
+        open
+    }
+
+    fn apply(&mut self, step: &Step) {
+        for (cell, token) in &step.placements {
+            self.place(*cell, *token);
+        }
+        for (cell, token) in &step.eliminations {
+            self.candidates[self.size.index(*cell)].remove(*token);
+        }
+    }
+
+    fn place(&mut self, cell: Cell, token: Token) {
+        self.game.set_internal(cell, token);
+        self.candidates[self.size.index(cell)] = Candidates::empty();
+        let peers = self.peers(cell).collect::<Vec<_>>();
+        for peer in peers {
+            self.candidates[self.size.index(peer)].remove(token);
+        }
+
+        // Constraints can rule out candidates anywhere on the board
+        if !self.game.constraints().is_empty() {
+            for cell in BoardIndexer::new(self.size) {
+                let index = self.size.index(cell);
+                self.candidates[index] = self.candidates[index] & self.game.candidates(cell);
+            }
+        }
+    }
+
+    fn positions(&self, unit: &[Cell], token: Token) -> Vec<Cell> {
+        unit.iter()
+            .copied()
+            .filter(|cell| self.candidates(*cell).contains(token))
+            .collect()
+    }
+
+    fn cells_with(&self, token: Token) -> Vec<Cell> {
+        self.cells()
+            .filter(|cell| self.candidates(*cell).contains(token))
+            .collect()
+    }
+
+    // Pairs of cells that are the only two places for the token in some unit
+    fn conjugates(&self, token: Token) -> Vec<(Cell, Cell)> {
+        let mut conjugates = Vec::new();
+        for unit in &self.units {
+            if let [first, second] = self.positions(unit, token)[..] {
+                if !conjugates.contains(&(first, second)) {
+                    conjugates.push((first, second));
+                }
+            }
+        }
+        conjugates
+    }
+
+    fn eliminations_from(
+        &self,
+        cells: impl Iterator<Item = Cell>,
+        token: Token,
+    ) -> Vec<(Cell, Token)> {
+        cells
+            .filter(|cell| self.candidates(*cell).contains(token))
+            .map(|cell| (cell, token))
+            .collect()
+    }
+}
+
+fn units(game: &Game) -> Units {
+    let size = game.size();
+    let regions = game.regions();
+    let rows = (0..size.side()).map(|i| RowIndexer::new(size, i).collect());
+    let columns = (0..size.side()).map(|i| ColumnIndexer::new(size, i).collect());
+    let sectors = (0..size.side()).map(|i| regions.cells(i).to_vec());
+    rows.chain(columns)
+        .chain(sectors)
+        .chain(ops::extra_units(game))
+        .collect()
+}
+
+fn step(
+    technique: Technique,
+    cells: Vec<Cell>,
+    unit: Option<usize>,
+    eliminations: Vec<(Cell, Token)>,
+) -> Option<Step> {
+    if eliminations.is_empty() {
+        None
+    } else {
+        Some(Step {
+            technique,
+            cells,
+            unit,
+            eliminations,
+            placements: Vec::new(),
+        })
+    }
+}
+
+fn placement(technique: Technique, unit: Option<usize>, cell: Cell, token: Token) -> Step {
+    Step {
+        technique,
+        cells: vec![cell],
+        unit,
+        eliminations: Vec::new(),
+        placements: vec![(cell, token)],
+    }
+}
+
+fn find_combination<T: Copy, R>(
+    items: &[T],
+    size: usize,
+    check: &mut dyn FnMut(&[T]) -> Option<R>,
+) -> Option<R> {
+    fn recurse<T: Copy, R>(
+        items: &[T],
+        size: usize,
+        start: usize,
+        chosen: &mut Vec<T>,
+        check: &mut dyn FnMut(&[T]) -> Option<R>,
+    ) -> Option<R> {
+        if chosen.len() == size {
+            return check(chosen);
+        }
+
+        for index in start..items.len() {
+            chosen.push(items[index]);
+            let found = recurse(items, size, index + 1, chosen, check);
+            if found.is_some() {
+                return found;
+            }
+            chosen.pop();
+        }
+        None
+    }
+
+    recurse(items, size, 0, &mut Vec::with_capacity(size), check)
+}
+
+fn naked_single(grid: &Grid) -> Option<Step> {
+    grid.cells().find_map(|cell| {
+        grid.candidates(cell)
+            .single()
+            .map(|token| placement(Technique::NakedSingle, None, cell, token))
+    })
+}
+
+fn hidden_single(grid: &Grid) -> Option<Step> {
+    grid.units.iter().enumerate().find_map(|(index, unit)| {
+        grid.tokens().iter().find_map(|token| {
+            if let [cell] = grid.positions(unit, *token)[..] {
+                Some(placement(
+                    Technique::HiddenSingle,
+                    Some(index),
+                    cell,
+                    *token,
+                ))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+// A token confined to a single line inside of a sector cannot be elsewhere in that line
+fn pointing(grid: &Grid) -> Option<Step> {
+    let side = grid.size.side();
+    grid.sectors()
+        .iter()
+        .enumerate()
+        .find_map(|(index, sector)| {
+            grid.tokens().iter().find_map(|token| {
+                let positions = grid.positions(sector, *token);
+                let first = *positions.first()?;
+
+                let line = if positions.iter().all(|cell| cell.row() == first.row()) {
+                    &grid.rows()[first.row()]
+                } else if positions.iter().all(|cell| cell.column() == first.column()) {
+                    &grid.columns()[first.column()]
+                } else {
+                    return None;
+                };
+
+                let sector = grid.regions.region(first);
+                let outside = line
+                    .iter()
+                    .copied()
+                    .filter(|cell| grid.regions.region(*cell) != sector);
+                let eliminations = grid.eliminations_from(outside, *token);
+                step(
+                    Technique::Pointing,
+                    positions,
+                    Some(2 * side + index),
+                    eliminations,
+                )
+            })
+        })
+}
+
+// A token confined to a single sector inside of a line cannot be elsewhere in that sector
+fn claiming(grid: &Grid) -> Option<Step> {
+    let side = grid.size.side();
+    grid.units[..2 * side]
+        .iter()
+        .enumerate()
+        .find_map(|(index, line)| {
+            grid.tokens().iter().find_map(|token| {
+                let positions = grid.positions(line, *token);
+                let first = *positions.first()?;
+                let sector = grid.regions.region(first);
+                if !positions
+                    .iter()
+                    .all(|cell| grid.regions.region(*cell) == sector)
+                {
+                    return None;
+                }
+
+                let outside = grid.sectors()[sector]
+                    .iter()
+                    .copied()
+                    .filter(|cell| !line.contains(cell));
+                let eliminations = grid.eliminations_from(outside, *token);
+                step(Technique::Claiming, positions, Some(index), eliminations)
+            })
+        })
+}
+
+fn naked_pair(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 2, Technique::NakedPair)
+}
+
+fn naked_triple(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 3, Technique::NakedTriple)
+}
+
+fn naked_quad(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 4, Technique::NakedQuad)
+}
+
+// N cells in a unit sharing only N candidates claim those candidates for themselves
+fn naked_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    grid.units.iter().enumerate().find_map(|(index, unit)| {
+        let cells = unit
+            .iter()
+            .copied()
+            .filter(|cell| (2..=size).contains(&grid.candidates(*cell).len()))
+            .collect::<Vec<_>>();
+
+        find_combination(&cells, size, &mut |subset| {
+            let union = subset.iter().fold(Candidates::empty(), |union, cell| {
+                union | grid.candidates(*cell)
+            });
+            if union.len() != size {
+                return None;
+            }
+
+            let eliminations = unit
+                .iter()
+                .filter(|cell| !subset.contains(cell))
+                .flat_map(|cell| {
+                    (grid.candidates(*cell) & union)
+                        .iter()
+                        .map(move |token| (*cell, token))
+                })
+                .collect();
+            step(technique, subset.to_vec(), Some(index), eliminations)
+        })
+    })
+}
+
+fn hidden_pair(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 2, Technique::HiddenPair)
+}
+
+fn hidden_triple(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 3, Technique::HiddenTriple)
+}
+
+fn hidden_quad(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 4, Technique::HiddenQuad)
+}
+
+// N candidates that can only go in the same N cells of a unit claim those cells for themselves
+fn hidden_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    grid.units.iter().enumerate().find_map(|(index, unit)| {
+        let tokens = grid
+            .tokens()
+            .iter()
+            .copied()
+            .filter(|token| (1..=size).contains(&grid.positions(unit, *token).len()))
+            .collect::<Vec<_>>();
+
+        find_combination(&tokens, size, &mut |subset| {
+            let mut cells = Vec::with_capacity(size);
+            for token in subset {
+                for cell in grid.positions(unit, *token) {
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+            if cells.len() != size {
+                return None;
+            }
+
+            let mut kept = Candidates::empty();
+            for token in subset {
+                kept.insert(*token);
+            }
+
+            let eliminations = cells
+                .iter()
+                .flat_map(|cell| {
+                    (grid.candidates(*cell) & !kept)
+                        .iter()
+                        .map(move |token| (*cell, token))
+                })
+                .collect();
+            step(technique, cells, Some(index), eliminations)
+        })
+    })
+}
+
+fn x_wing(grid: &Grid) -> Option<Step> {
+    fish(grid, 2, Technique::XWing)
+}
+
+fn swordfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 3, Technique::Swordfish)
+}
+
+fn jellyfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 4, Technique::Jellyfish)
+}
+
+// When a token is confined to the same N columns across N rows, those columns are covered by
+// these rows and the token can be removed from the rest of them (and the same, swapping lines)
+fn fish(grid: &Grid, count: usize, technique: Technique) -> Option<Step> {
+    grid.tokens().iter().find_map(|token| {
+        let side = grid.size.side();
+        [(0, side), (side, 0)].iter().find_map(|(base, cover)| {
+            let lines = (0..side)
+                .filter_map(|line| {
+                    let positions = grid.positions(&grid.units[base + line], *token);
+                    if (2..=count).contains(&positions.len()) {
+                        Some((line, cover_indices(&positions, *base)))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            find_combination(&lines, count, &mut |subset| {
+                let covered = subset.iter().fold(0_u32, |union, line| union | line.1);
+                if covered.count_ones() as usize != count {
+                    return None;
+                }
+
+                let outside = (0..side)
+                    .filter(|index| covered & (1 << index) != 0)
+                    .flat_map(|index| grid.units[cover + index].iter().copied())
+                    .filter(|cell| {
+                        let line = if *base == 0 {
+                            cell.row()
+                        } else {
+                            cell.column()
+                        };
+                        !subset.iter().any(|base_line| base_line.0 == line)
+                    });
+                let cells = subset
+                    .iter()
+                    .flat_map(|line| grid.positions(&grid.units[base + line.0], *token))
+                    .collect();
+                step(
+                    technique,
+                    cells,
+                    None,
+                    grid.eliminations_from(outside, *token),
+                )
+            })
+        })
+    })
+}
+
+fn cover_indices(cells: &[Cell], base: usize) -> u32 {
+    cells.iter().fold(0, |mask, cell| {
+        let index = if base == 0 { cell.column() } else { cell.row() };
+        mask | (1 << index)
+    })
+}
+
+// A bivalue pivot {x, y} seeing the pincers {x, z} and {y, z} forces z into one of the pincers
+fn xy_wing(grid: &Grid) -> Option<Step> {
+    let bivalues = grid
+        .cells()
+        .filter(|cell| grid.candidates(*cell).len() == 2)
+        .collect::<Vec<_>>();
+
+    bivalues.iter().find_map(|pivot| {
+        let pivot_candidates = grid.candidates(*pivot);
+        bivalues.iter().find_map(|first| {
+            let shared = grid.candidates(*first) & pivot_candidates;
+            if !grid.sees(*pivot, *first) || shared.len() != 1 {
+                return None;
+            }
+
+            let z = (grid.candidates(*first) & !shared).single()?;
+            let mut expected = pivot_candidates & !shared;
+            expected.insert(z);
+
+            bivalues.iter().find_map(|second| {
+                if second == first || !grid.sees(*pivot, *second) {
+                    return None;
+                }
+                if grid.candidates(*second) != expected {
+                    return None;
+                }
+
+                let targets = grid.cells().filter(|cell| {
+                    grid.sees(*cell, *first) && grid.sees(*cell, *second) && cell != pivot
+                });
+                step(
+                    Technique::XyWing,
+                    vec![*pivot, *first, *second],
+                    None,
+                    grid.eliminations_from(targets, z),
+                )
+            })
+        })
+    })
+}
+
+// A trivalue pivot {x, y, z} seeing the pincers {x, z} and {y, z} forces z into one of the three
+fn xyz_wing(grid: &Grid) -> Option<Step> {
+    let bivalues = grid
+        .cells()
+        .filter(|cell| grid.candidates(*cell).len() == 2)
+        .collect::<Vec<_>>();
+
+    grid.cells()
+        .filter(|cell| grid.candidates(*cell).len() == 3)
+        .find_map(|pivot| {
+            let pivot_candidates = grid.candidates(pivot);
+            let pincers = bivalues
+                .iter()
+                .copied()
+                .filter(|cell| {
+                    grid.sees(pivot, *cell)
+                        && grid.candidates(*cell) & pivot_candidates == grid.candidates(*cell)
+                })
+                .collect::<Vec<_>>();
+
+            find_combination(&pincers, 2, &mut |pair| {
+                let (first, second) = (pair[0], pair[1]);
+                if grid.candidates(first) | grid.candidates(second) != pivot_candidates {
+                    return None;
+                }
+
+                let z = (grid.candidates(first) & grid.candidates(second)).single()?;
+                let targets = grid.cells().filter(|cell| {
+                    grid.sees(*cell, pivot) && grid.sees(*cell, first) && grid.sees(*cell, second)
+                });
+                step(
+                    Technique::XyzWing,
+                    vec![pivot, first, second],
+                    None,
+                    grid.eliminations_from(targets, z),
+                )
+            })
+        })
+}
+
+// Chains of conjugate pairs alternate between true and false, so one of the two colors holds.
+// A color that sees itself is false, and a cell that sees both colors cannot hold the token
+fn simple_coloring(grid: &Grid) -> Option<Step> {
+    grid.tokens().iter().find_map(|token| {
+        let conjugates = grid.conjugates(*token);
+        let mut colors: Vec<Option<bool>> = vec![None; grid.size.cells()];
+
+        for (start, _) in &conjugates {
+            if colors[grid.size.index(*start)].is_some() {
+                continue;
+            }
+
+            let mut component = vec![*start];
+            colors[grid.size.index(*start)] = Some(true);
+            let mut index = 0;
+            while index < component.len() {
+                let cell = component[index];
+                let color = colors[grid.size.index(cell)].map(|color| !color);
+                for (first, second) in &conjugates {
+                    let next = if *first == cell {
+                        *second
+                    } else if *second == cell {
+                        *first
+                    } else {
+                        continue;
+                    };
+                    if colors[grid.size.index(next)].is_none() {
+                        colors[grid.size.index(next)] = color;
+                        component.push(next);
+                    }
+                }
+                index += 1;
+            }
+
+            let colors = &colors;
+            let colored = |color| {
+                component
+                    .iter()
+                    .copied()
+                    .filter(move |cell| colors[grid.size.index(*cell)] == Some(color))
+            };
+
+            for color in [true, false] {
+                let wrapped =
+                    colored(color).any(|cell| colored(color).any(|other| grid.sees(cell, other)));
+                if wrapped {
+                    return step(
+                        Technique::SimpleColoring,
+                        component.clone(),
+                        None,
+                        grid.eliminations_from(colored(color), *token),
+                    );
+                }
+            }
+
+            let trapped = grid.cells_with(*token).into_iter().filter(|cell| {
+                colors[grid.size.index(*cell)].is_none()
+                    && colored(true).any(|other| grid.sees(*cell, other))
+                    && colored(false).any(|other| grid.sees(*cell, other))
+            });
+            let found = step(
+                Technique::SimpleColoring,
+                component.clone(),
+                None,
+                grid.eliminations_from(trapped, *token),
+            );
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    })
+}
+
+// Alternating chains that start and end on strong links guarantee that one of the ends holds the
+// token, so any cell seeing both ends cannot hold it
+fn x_chain(grid: &Grid) -> Option<Step> {
+    grid.tokens().iter().find_map(|token| {
+        let conjugates = grid.conjugates(*token);
+        let cells = grid.cells_with(*token);
+
+        cells.iter().find_map(|start| {
+            // Each cell is visited at most once after a weak link and once after a strong link
+            let mut visited = vec![[false; 2]; grid.size.cells()];
+            let mut queue = vec![(*start, false)];
+            visited[grid.size.index(*start)][0] = true;
+            let mut index = 0;
+
+            while index < queue.len() {
+                let (cell, strong) = queue[index];
+                index += 1;
+
+                if strong {
+                    if cell != *start {
+                        let targets = cells
+                            .iter()
+                            .copied()
+                            .filter(|other| grid.sees(*other, *start) && grid.sees(*other, cell));
+                        let found = step(
+                            Technique::XChain,
+                            vec![*start, cell],
+                            None,
+                            grid.eliminations_from(targets, *token),
+                        );
+                        if found.is_some() {
+                            return found;
+                        }
+                    }
+
+                    for next in cells
+                        .iter()
+                        .copied()
+                        .filter(|other| grid.sees(cell, *other))
+                    {
+                        if !visited[grid.size.index(next)][0] {
+                            visited[grid.size.index(next)][0] = true;
+                            queue.push((next, false));
+                        }
+                    }
+                } else {
+                    for (first, second) in &conjugates {
+                        let next = if *first == cell {
+                            *second
+                        } else if *second == cell {
+                            *first
+                        } else {
+                            continue;
+                        };
+                        if !visited[grid.size.index(next)][1] {
+                            visited[grid.size.index(next)][1] = true;
+                            queue.push((next, true));
+                        }
+                    }
+                }
+            }
+
+            None
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Technique};
+    use crate::game::{ops, solver, Candidates, Cell, Difficulty, Game, Size, Symmetry, Token};
+    use crate::index::BoardIndexer;
+
+    // Builds a grid on an empty board where only the listed cells hold the token as a candidate
+    fn grid_with(token: Token, cells: &[(usize, usize)]) -> Grid {
+        let mut grid = Grid::new(&Game::new_empty(Size::STANDARD));
+        for candidates in &mut grid.candidates {
+            candidates.remove(token);
+        }
+        for (row, column) in cells {
+            grid.candidates[Size::STANDARD.index(Cell::new(*row, *column))].insert(token);
+        }
+        grid
+    }
+
+    fn set_candidates(grid: &mut Grid, row: usize, column: usize, tokens: &[Token]) {
+        let mut candidates = Candidates::empty();
+        for token in tokens {
+            candidates.insert(*token);
+        }
+        grid.candidates[Size::STANDARD.index(Cell::new(row, column))] = candidates;
+    }
+
+    fn assert_sound(game: &Game) {
+        let solution = ops::solutions(game, 2);
+        assert_eq!(solution.len(), 1);
+        let solution = &solution[0];
+
+        let trace = super::solve(game);
+        for step in &trace.steps {
+            for (cell, token) in &step.placements {
+                assert_eq!(solution.get(*cell), *token, "{step}");
+            }
+            for (cell, token) in &step.eliminations {
+                assert_ne!(solution.get(*cell), *token, "{step}");
+            }
+        }
+
+        for cell in BoardIndexer::new(game.size()) {
+            let token = trace.game.get(cell);
+            if token != Token::None {
+                assert_eq!(token, solution.get(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn solve_puzzles() {
+        for puzzle in solver::puzzles() {
+            if ops::count_solutions(&puzzle, 2) == 1 {
+                assert_sound(&puzzle);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_hard() {
+        let game = Game::from(ops::hard_board());
+        assert_sound(&game);
+
+        // This puzzle needs techniques beyond the ones implemented
+        let trace = super::solve(&game);
+        assert!(!trace.is_solved());
+        assert_eq!(trace.difficulty(), Difficulty::Diabolical);
+    }
+
+    #[test]
+    fn solve_generated() {
+        for difficulty in &[Difficulty::Medium, Difficulty::Hard, Difficulty::Expert] {
+            for _ in 0..4 {
+                let mut rng = rand::thread_rng();
+                if let Some(puzzle) = Game::new_solved(Size::STANDARD, &mut rng).prune(
+                    *difficulty,
+                    Symmetry::None,
+                    &mut rng,
+                ) {
+                    assert_sound(&puzzle);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve_easy() {
+        let mut game = Game::from(ops::consistent_board());
+        for cell in crate::index::SectorIndexer::new(Size::STANDARD, 4) {
+            game.set_internal(cell, Token::None);
+        }
+
+        let trace = super::solve(&game);
+        assert!(trace.is_solved());
+        assert_eq!(trace.steps.len(), 9);
+        assert_eq!(trace.hardest(), Some(Technique::NakedSingle));
+        assert_eq!(trace.difficulty(), Difficulty::Beginner);
+    }
+
+    #[test]
+    fn solve_rectangular() {
+        let mut rng = rand::thread_rng();
+        for size in &[Size::new(2, 3).unwrap(), Size::new(3, 4).unwrap()] {
+            let solved = Game::new_solved(*size, &mut rng);
+            let mut game = solved.clone();
+            for cell in crate::index::SectorIndexer::new(*size, 1) {
+                game.set_internal(cell, Token::None);
+            }
+
+            let trace = super::solve(&game);
+            assert!(trace.is_solved());
+            for cell in BoardIndexer::new(*size) {
+                assert_eq!(trace.game.get(cell), solved.get(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn pointing() {
+        let grid = grid_with(Token::Five, &[(0, 0), (0, 1), (0, 5), (0, 8), (3, 3)]);
+        let step = super::pointing(&grid).unwrap();
+        assert_eq!(step.technique, Technique::Pointing);
+        assert_eq!(
+            step.eliminations,
+            vec![
+                (Cell::new(0, 5), Token::Five),
+                (Cell::new(0, 8), Token::Five)
+            ]
+        );
+    }
+
+    #[test]
+    fn claiming() {
+        let grid = grid_with(Token::Five, &[(0, 0), (0, 1), (1, 2), (2, 0), (3, 3)]);
+        let step = super::claiming(&grid).unwrap();
+        assert_eq!(step.technique, Technique::Claiming);
+        assert_eq!(
+            step.eliminations,
+            vec![
+                (Cell::new(1, 2), Token::Five),
+                (Cell::new(2, 0), Token::Five)
+            ]
+        );
+    }
+
+    #[test]
+    fn naked_pair() {
+        let mut grid = Grid::new(&Game::new_empty(Size::STANDARD));
+        set_candidates(&mut grid, 4, 1, &[Token::Two, Token::Seven]);
+        set_candidates(&mut grid, 4, 6, &[Token::Two, Token::Seven]);
+
+        let step = super::naked_pair(&grid).unwrap();
+        assert_eq!(step.technique, Technique::NakedPair);
+        assert_eq!(step.eliminations.len(), 14);
+        for (cell, token) in step.eliminations {
+            assert_eq!(cell.row(), 4);
+            assert!(token == Token::Two || token == Token::Seven);
+        }
+    }
+
+    #[test]
+    fn hidden_pair() {
+        let mut grid = grid_with(Token::Three, &[(2, 2), (2, 5), (5, 0), (7, 1)]);
+        for candidates in &mut grid.candidates {
+            candidates.remove(Token::Eight);
+        }
+        grid.candidates[Size::STANDARD.index(Cell::new(2, 2))].insert(Token::Eight);
+        grid.candidates[Size::STANDARD.index(Cell::new(2, 5))].insert(Token::Eight);
+
+        let step = super::hidden_pair(&grid).unwrap();
+        assert_eq!(step.technique, Technique::HiddenPair);
+        assert_eq!(step.eliminations.len(), 14);
+        for (cell, token) in step.eliminations {
+            assert!(cell == Cell::new(2, 2) || cell == Cell::new(2, 5));
+            assert!(token != Token::Three && token != Token::Eight);
+        }
+    }
+
+    #[test]
+    fn x_wing() {
+        let grid = grid_with(
+            Token::Four,
+            &[(1, 2), (1, 7), (6, 2), (6, 7), (4, 2), (8, 7), (4, 4)],
+        );
+        let step = super::x_wing(&grid).unwrap();
+        assert_eq!(step.technique, Technique::XWing);
+        assert_eq!(
+            step.eliminations,
+            vec![
+                (Cell::new(4, 2), Token::Four),
+                (Cell::new(8, 7), Token::Four)
+            ]
+        );
+    }
+
+    #[test]
+    fn swordfish() {
+        let grid = grid_with(
+            Token::Nine,
+            &[
+                (0, 0),
+                (0, 4),
+                (3, 4),
+                (3, 8),
+                (7, 0),
+                (7, 8),
+                (5, 0),
+                (5, 1),
+                (5, 2),
+            ],
+        );
+        let step = super::swordfish(&grid).unwrap();
+        assert_eq!(step.technique, Technique::Swordfish);
+        assert_eq!(step.eliminations, vec![(Cell::new(5, 0), Token::Nine)]);
+    }
+
+    #[test]
+    fn xy_wing() {
+        let mut grid = Grid::new(&Game::new_empty(Size::STANDARD));
+        set_candidates(&mut grid, 0, 0, &[Token::One, Token::Two]);
+        set_candidates(&mut grid, 0, 5, &[Token::One, Token::Three]);
+        set_candidates(&mut grid, 5, 0, &[Token::Two, Token::Three]);
+
+        let step = super::xy_wing(&grid).unwrap();
+        assert_eq!(step.technique, Technique::XyWing);
+        assert_eq!(step.eliminations, vec![(Cell::new(5, 5), Token::Three)]);
+    }
+
+    #[test]
+    fn xyz_wing() {
+        let mut grid = Grid::new(&Game::new_empty(Size::STANDARD));
+        set_candidates(&mut grid, 0, 0, &[Token::One, Token::Two, Token::Three]);
+        set_candidates(&mut grid, 0, 6, &[Token::One, Token::Three]);
+        set_candidates(&mut grid, 1, 1, &[Token::Two, Token::Three]);
+
+        let step = super::xyz_wing(&grid).unwrap();
+        assert_eq!(step.technique, Technique::XyzWing);
+        assert_eq!(
+            step.eliminations,
+            vec![
+                (Cell::new(0, 1), Token::Three),
+                (Cell::new(0, 2), Token::Three)
+            ]
+        );
+    }
+
+    #[test]
+    fn simple_coloring_trap() {
+        let grid = grid_with(
+            Token::Six,
+            &[(0, 0), (0, 4), (0, 8), (5, 0), (5, 6), (2, 6), (1, 7)],
+        );
+        let step = super::simple_coloring(&grid).unwrap();
+        assert_eq!(step.technique, Technique::SimpleColoring);
+        assert_eq!(step.eliminations, vec![(Cell::new(0, 8), Token::Six)]);
+    }
+
+    #[test]
+    fn simple_coloring_wrap() {
+        let grid = grid_with(Token::Six, &[(0, 0), (0, 6), (2, 7), (2, 1), (1, 1)]);
+        let step = super::simple_coloring(&grid).unwrap();
+        assert_eq!(step.technique, Technique::SimpleColoring);
+        assert_eq!(
+            step.eliminations,
+            vec![
+                (Cell::new(0, 0), Token::Six),
+                (Cell::new(2, 7), Token::Six),
+                (Cell::new(1, 1), Token::Six),
+            ]
+        );
+    }
+
+    #[test]
+    fn x_chain() {
+        let grid = grid_with(
+            Token::Two,
+            &[(0, 0), (0, 4), (0, 8), (5, 0), (5, 6), (2, 6), (1, 7)],
+        );
+        let step = super::x_chain(&grid).unwrap();
+        assert_eq!(step.technique, Technique::XChain);
+        assert_eq!(step.eliminations, vec![(Cell::new(0, 8), Token::Two)]);
+    }
+
+    #[test]
+    fn step_display() {
+        let grid = grid_with(Token::Four, &[(1, 2), (1, 7), (6, 2), (6, 7), (4, 2)]);
+        let step = super::x_wing(&grid).unwrap();
+        assert_eq!(step.to_string(), "X-Wing: r5c3<>4");
+
+        let grid = grid_with(Token::Four, &[(1, 2)]);
+        let step = super::next_step(&grid).unwrap();
+        assert_eq!(step.to_string(), "Hidden Single: r2c3=4");
+    }
+}
@@ -1,272 +1,412 @@
-use super::{Cell, Difficulty, Game, Token};
+use super::{regions, Cage, Cages, Candidates, Cell, Difficulty, Game, Size, Symmetry, Token};
+use crate::index::{BoardIndexer, ColumnIndexer, RowIndexer};
 
 pub fn consistent(game: &Game, cell: Cell, reference: Token) -> bool {
     if reference == Token::None {
         return true;
     }
 
-    let row = cell.row();
-    let col = cell.column();
-    let mut index = 0;
-    for token in game.board[row * 9..(row + 1) * 9].iter() {
-        if *token == reference && index != col {
-            return false;
-        }
-        index += 1;
+    if !game
+        .constraints
+        .iter()
+        .all(|constraint| constraint.allows(game, cell, reference))
+    {
+        return false;
     }
 
-    index = 0;
-    for token in &game.columns[col] {
-        if *token == reference && index != row {
-            return false;
-        }
-        index += 1;
+    let size = game.size;
+    let sector = game.regions.region(cell);
+    let mask = game.rows[cell.row()] | game.columns[cell.column()] | game.sectors[sector];
+    if mask & reference.mask() == 0 {
+        return true;
     }
 
-    let sector_index = cell.sector_index();
-    index = 0;
-    for token in &game.sectors[cell.sector()] {
-        if *token == reference && index != sector_index {
-            return false;
-        }
-        index += 1;
+    if game.get(cell) != reference {
+        return false;
     }
 
-    true
+    // The cell itself accounts for the token in the masks, so the peers must be checked
+    RowIndexer::new(size, cell.row())
+        .chain(ColumnIndexer::new(size, cell.column()))
+        .chain(game.regions.cells(sector).iter().copied())
+        .all(|peer| peer == cell || game.get(peer) != reference)
+}
+
+pub fn solved(game: &Game) -> bool {
+    BoardIndexer::new(game.size).all(|cell| {
+        let token = game.get(cell);
+        token != Token::None && consistent(game, cell, token)
+    })
+}
+
+// Whether no placed token breaks a rule, filled or not
+pub fn valid(game: &Game) -> bool {
+    BoardIndexer::new(game.size).all(|cell| consistent(game, cell, game.get(cell)))
+}
+
+// The placed tokens that break a rule, in board order
+pub fn clashes(game: &Game) -> Vec<Cell> {
+    BoardIndexer::new(game.size)
+        .filter(|cell| !consistent(game, *cell, game.get(*cell)))
+        .collect()
+}
+
+// Unique, and removing any of the clues breaks the uniqueness
+pub fn minimal(game: &Game) -> bool {
+    count_solutions(game, 2) == 1
+        && BoardIndexer::new(game.size)
+            .filter(|cell| game.get(*cell) != Token::None)
+            .all(|cell| {
+                let mut pruned = game.clone();
+                pruned.set_internal(cell, Token::None);
+                count_solutions(&pruned, 2) > 1
+            })
 }
 
-pub fn generate_solved() -> Game {
-    let mut board = [Token::None; 81];
+pub fn generate_solved(size: Size, rng: &mut impl rand::Rng) -> Game {
+    let mut board = vec![Token::None; size.cells()];
 
-    board[0..9].copy_from_slice(&random_token_sequence()[..]);
-    let game = Game::from(board);
-    if let Some(solved) = solve(&game, false, true) {
+    board[0..size.side()].copy_from_slice(&random_token_sequence(size, rng)[..]);
+    let game = Game::with_tokens(size, board);
+    if let Some(solved) = solve(&game, rng) {
         solved
     } else {
         unreachable!();
     }
 }
 
-pub fn prune_per_gaps(game: &Game, max_difficulty: Difficulty) -> [Option<Game>; 3] {
-    let mut current_game = *game;
-    let mut pruned = [None; 3];
-    let sequence = random_filled_sequence(game);
-    let mut removed = 81 - sequence.len();
-
-    for cell in sequence {
-        if can_remove(current_game, cell) {
-            current_game.set_internal(cell, Token::None);
-            removed += 1;
-
-            if removed == Difficulty::Easy.to_gaps() {
-                pruned[0] = Some(current_game);
-            } else if removed == Difficulty::Medium.to_gaps() {
-                pruned[1] = Some(current_game);
-            }
+// Removes clues in a random order for as long as the solution stays unique and the puzzle does
+// not get harder than requested. Clues are removed a whole symmetry orbit at a time.
+// Returns `None` if the result does not land in the requested band
+pub fn prune(
+    game: &Game,
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut impl rand::Rng,
+) -> Option<Game> {
+    let mut current_game = game.clone();
+
+    for cell in random_filled_sequence(game, rng) {
+        if current_game.get(cell) == Token::None {
+            continue;
+        }
 
-            if removed == max_difficulty.to_gaps() {
-                break;
-            }
+        let mut pruned = current_game.clone();
+        for cell in symmetry.orbit(game.size, cell) {
+            pruned.set_internal(cell, Token::None);
+        }
+        // Puzzles that the logical solver finishes have a unique solution, which saves the search
+        let rating = pruned.rate();
+        if rating <= difficulty
+            && (rating < Difficulty::Diabolical || count_solutions(&pruned, 2) == 1)
+        {
+            current_game = pruned;
         }
     }
 
-    if removed > Difficulty::Medium.to_gaps() {
-        pruned[2] = Some(current_game);
-    } else if removed > Difficulty::Easy.to_gaps() {
-        pruned[1] = Some(current_game);
-    } else if removed > 0 {
-        pruned[0] = Some(current_game);
+    if current_game.rate() == difficulty {
+        Some(current_game.with_givens())
+    } else {
+        None
     }
-
-    pruned
 }
 
-pub fn prune_per_duration(game: &Game, max_difficulty: Difficulty) -> [Option<Game>; 3] {
-    let mut current_game = *game;
-    let mut pruned = [None; 3];
-    let sequence = random_filled_sequence(game);
-
-    let start_time = std::time::Instant::now();
-    let mut changed = false;
-    for cell in sequence {
-        if can_remove(current_game, cell) {
-            current_game.set_internal(cell, Token::None);
-            changed = true;
-        }
-
-        // TODO this is broken
-        let elapsed = start_time.elapsed();
-        if elapsed > Difficulty::Easy.to_duration() {
-            if elapsed > Difficulty::Medium.to_duration() {
-                pruned[1] = Some(current_game);
-                changed = false;
-            } else {
-                pruned[0] = Some(current_game);
-                changed = false;
-            }
-        }
+// Cages grow from random cells through neighbours that do not repeat a token. As long as the cages
+// allow another solution, a cell where it differs is split off into a cage of its own, which gives
+// its token away
+pub fn generate_killer(solved: &Game, rng: &mut impl rand::Rng) -> Game {
+    use rand::seq::IteratorRandom;
 
-        if start_time.elapsed() >= max_difficulty.to_duration() {
-            break;
-        }
+    let size = solved.size;
+    let mut empty = solved.clone();
+    for cell in BoardIndexer::new(size) {
+        empty.set_internal(cell, Token::None);
+    }
+
+    let mut cages = random_cages(solved, rng);
+    loop {
+        let sums = cages
+            .iter()
+            .map(|cells| {
+                Cage::new(
+                    cells.iter().map(|cell| solved.get(*cell) as usize).sum(),
+                    cells.clone(),
+                )
+            })
+            .collect();
+        let puzzle = empty
+            .clone()
+            .with_cages(Cages::new(size, sums).expect("Cages are grown without repeats"));
+
+        let other = solutions(&puzzle, 2)
+            .into_iter()
+            .find(|solution| solution.board != solved.board);
+        let Some(other) = other else {
+            return puzzle;
+        };
+
+        let cell = BoardIndexer::new(size)
+            .filter(|cell| other.get(*cell) != solved.get(*cell))
+            .choose(rng)
+            .expect("Different solutions differ somewhere");
+        let index = cages
+            .iter()
+            .position(|cells| cells.contains(&cell))
+            .expect("Every cell is in a cage");
+        let mut rest = cages.swap_remove(index);
+        rest.retain(|other| *other != cell);
+        cages.push(vec![cell]);
+        cages.extend(regions::components(size, &rest));
     }
+}
+
+fn random_cages(solved: &Game, rng: &mut impl rand::Rng) -> Vec<Vec<Cell>> {
+    use rand::seq::SliceRandom;
+
+    const LARGEST: usize = 5;
 
-    let elapsed = start_time.elapsed();
-    if elapsed > Difficulty::Medium.to_duration() {
-        if changed {
-            pruned[2] = Some(current_game);
+    let size = solved.size;
+    let mut taken = vec![false; size.cells()];
+    let mut cages = Vec::new();
+    for cell in random_sequence(size, rng) {
+        if taken[size.index(cell)] {
+            continue;
         }
-    } else if elapsed > Difficulty::Easy.to_duration() {
-        if changed {
-            pruned[1] = Some(current_game);
+
+        let length = rng.gen_range(2, LARGEST + 1);
+        let mut cage = vec![cell];
+        taken[size.index(cell)] = true;
+        while cage.len() < length {
+            let options = cage
+                .iter()
+                .flat_map(|cell| regions::neighbours(size, *cell))
+                .filter(|neighbour| {
+                    !taken[size.index(*neighbour)]
+                        && cage
+                            .iter()
+                            .all(|cell| solved.get(*cell) != solved.get(*neighbour))
+                })
+                .collect::<Vec<_>>();
+            let Some(next) = options.choose(rng) else {
+                break;
+            };
+            taken[size.index(*next)] = true;
+            cage.push(*next);
         }
-    } else if changed {
-        pruned[0] = Some(current_game);
+        cages.push(cage);
     }
+    cages
+}
 
-    pruned
+pub fn count_solutions(game: &Game, limit: usize) -> usize {
+    solutions(game, limit).len()
 }
 
-fn can_remove(mut game: Game, cell: Cell) -> bool {
-    let original = game.get(cell);
+pub fn solutions(game: &Game, limit: usize) -> Vec<Game> {
+    let mut solutions = Vec::new();
+    search(game, limit, &mut |_| {}, &mut solutions);
+    solutions
+}
 
-    for token in Token::list() {
-        if *token != original && consistent(&game, cell, *token) {
-            game.set_internal(cell, *token);
-            if solve(&game, true, false).is_some() {
-                return false;
-            }
-        }
+pub fn solve(game: &Game, rng: &mut impl rand::Rng) -> Option<Game> {
+    use rand::seq::SliceRandom;
+
+    let mut solutions = Vec::with_capacity(1);
+    search(game, 1, &mut |tokens| tokens.shuffle(rng), &mut solutions);
+    solutions.pop()
+}
+
+fn search(
+    game: &Game,
+    limit: usize,
+    order: &mut dyn FnMut(&mut [Token]),
+    solutions: &mut Vec<Game>,
+) {
+    if limit == 0 {
+        return;
+    }
+
+    if !valid(game) {
+        return;
     }
 
-    true
+    solve_depth(game.clone(), limit, order, solutions);
 }
 
-pub fn solve(game: &Game, maybe_parallel: bool, full_solution: bool) -> Option<Game> {
-    let sequnce_base = random_empty_sequence(game);
-    let sequence = if full_solution {
-        &sequnce_base[..]
-    } else {
-        &sequnce_base[0..sequnce_base.len() - 3]
+fn solve_depth(
+    mut game: Game,
+    limit: usize,
+    order: &mut dyn FnMut(&mut [Token]),
+    solutions: &mut Vec<Game>,
+) {
+    if !propagate(&mut game) {
+        return;
+    }
+
+    let Some(cell) = most_constrained(&game) else {
+        solutions.push(game);
+        return;
     };
 
-    if maybe_parallel && sequence.len() > 43 {
-        solve_parallel(game, &sequence)
-    } else {
-        let mut game_copy = *game;
-        if solve_depth(&mut game_copy, &sequence, 0) {
-            Some(game_copy)
-        } else {
-            None
+    let mut tokens = game.candidates(cell).iter().collect::<Vec<_>>();
+    order(&mut tokens);
+
+    for token in &tokens {
+        let mut branch = game.clone();
+        branch.set_internal(cell, *token);
+        solve_depth(branch, limit, order, solutions);
+        if solutions.len() >= limit {
+            return;
         }
     }
 }
 
-fn solve_parallel(game: &Game, sequence: &[Cell]) -> Option<Game> {
-    if sequence.is_empty() {
-        return Some(*game);
-    }
+// Places naked and hidden singles until there are none left. Returns false if the board reaches a
+// state where a cell has no candidates or a unit has nowhere to place a token
+pub fn propagate(game: &mut Game) -> bool {
+    loop {
+        let mut progress = false;
 
-    crossbeam_utils::thread::scope(|s| {
-        let cell = sequence[0];
-        let results = Token::list()
-            .iter()
-            .map(|token| {
-                s.spawn(move |_| {
-                    let mut thread_game = *game;
-                    if consistent(&thread_game, cell, *token) {
-                        thread_game.set_internal(cell, *token);
-                        if solve_depth(&mut thread_game, sequence, 1) {
-                            Some(thread_game)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
+        for cell in BoardIndexer::new(game.size) {
+            if game.get(cell) != Token::None {
+                continue;
+            }
 
-        results
-            .into_iter()
-            .map(crossbeam::thread::ScopedJoinHandle::join)
-            .filter_map(Result::ok)
-            .flatten()
-            .next()
-    })
-    .expect("Failed to start thread scope for solving game")
-}
+            let candidates = game.candidates(cell);
+            if candidates.is_empty() {
+                return false;
+            }
+            if let Some(token) = candidates.single() {
+                game.set_internal(cell, token);
+                progress = true;
+            }
+        }
 
-fn solve_depth(game: &mut Game, sequence: &[Cell], depth: usize) -> bool {
-    if depth == sequence.len() {
-        return true;
+        let size = game.size;
+        let regions = game.regions.clone();
+        for i in 0..size.side() {
+            for placed in [
+                place_hidden_singles(game, RowIndexer::new(size, i)),
+                place_hidden_singles(game, ColumnIndexer::new(size, i)),
+                place_hidden_singles(game, regions.cells(i).iter().copied()),
+            ] {
+                match placed {
+                    None => return false,
+                    Some(placed) => progress |= placed,
+                }
+            }
+        }
+        for unit in extra_units(game) {
+            match place_hidden_singles(game, unit.into_iter()) {
+                None => return false,
+                Some(placed) => progress |= placed,
+            }
+        }
+
+        if !progress {
+            return true;
+        }
     }
+}
 
-    let cell = sequence[depth];
-    for token in Token::list() {
-        if consistent(game, cell, *token) {
-            game.set_internal(cell, *token);
-            if solve_depth(game, sequence, depth + 1) {
-                return true;
-            }
+// Units that the constraints of the game add to the rows, columns and regions
+pub fn extra_units(game: &Game) -> Vec<Vec<Cell>> {
+    game.constraints
+        .iter()
+        .flat_map(|constraint| constraint.units(game.size))
+        .collect()
+}
+
+fn place_hidden_singles(game: &mut Game, unit: impl Iterator<Item = Cell>) -> Option<bool> {
+    let cells = unit.collect::<Vec<_>>();
+    let mut filled = 0;
+    let mut once = 0;
+    let mut twice = 0;
+
+    for cell in &cells {
+        let token = game.get(*cell);
+        if token == Token::None {
+            let candidates = game.candidates(*cell).0;
+            twice |= once & candidates;
+            once |= candidates;
+        } else {
+            filled |= token.mask();
         }
     }
 
-    game.set_internal(cell, Token::None);
-    false
+    if (once | filled) != game.size.mask() {
+        return None;
+    }
+
+    let singles = once & !twice;
+    if singles == 0 {
+        return Some(false);
+    }
+
+    for cell in cells {
+        let hidden = Candidates::from_mask(game.candidates(cell).0 & singles);
+        match hidden.len() {
+            0 => {}
+            1 => game.set_internal(cell, hidden.single()?),
+            _ => return None,
+        }
+    }
+    Some(true)
 }
 
-fn random_filled_sequence(game: &Game) -> Vec<Cell> {
-    let mut sequence = Vec::new();
+fn most_constrained(game: &Game) -> Option<Cell> {
+    let mut most_constrained = None;
+    let mut fewest = usize::MAX;
 
-    for cell in random_sequence().iter().map(Cell::from) {
+    for cell in BoardIndexer::new(game.size) {
         if game.get(cell) != Token::None {
-            sequence.push(cell);
+            continue;
+        }
+
+        let candidates = game.candidates(cell).len();
+        if candidates < fewest {
+            fewest = candidates;
+            most_constrained = Some(cell);
+            if candidates <= 2 {
+                break;
+            }
         }
     }
-    sequence
+
+    most_constrained
 }
 
-fn random_empty_sequence(game: &Game) -> Vec<Cell> {
+fn random_filled_sequence(game: &Game, rng: &mut impl rand::Rng) -> Vec<Cell> {
     let mut sequence = Vec::new();
 
-    for cell in random_sequence().iter().map(Cell::from) {
-        if game.get(cell) == Token::None {
+    for cell in random_sequence(game.size, rng) {
+        if game.get(cell) != Token::None {
             sequence.push(cell);
         }
     }
     sequence
 }
 
-fn random_sequence() -> [u8; 81] {
+fn random_sequence(size: Size, rng: &mut impl rand::Rng) -> Vec<Cell> {
     use rand::seq::SliceRandom;
 
-    let mut rng = rand::thread_rng();
-    let mut indices = [
-        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
-        25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47,
-        48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70,
-        71, 72, 73, 74, 75, 76, 77, 78, 79, 80,
-    ];
-    indices.shuffle(&mut rng);
-    indices
+    let mut cells = BoardIndexer::new(size).collect::<Vec<_>>();
+    cells.shuffle(rng);
+    cells
 }
 
-fn random_token_sequence() -> [Token; 9] {
+fn random_token_sequence(size: Size, rng: &mut impl rand::Rng) -> Vec<Token> {
     use rand::seq::SliceRandom;
 
-    let mut rng = rand::thread_rng();
-    let mut tokens = [Token::None; 9];
-    tokens[0..9].copy_from_slice(Token::list());
-    tokens.shuffle(&mut rng);
+    let mut tokens = size.tokens().to_vec();
+    tokens.shuffle(rng);
     tokens
 }
 
 #[cfg(test)]
-pub fn consistent_board() -> super::Board {
+pub fn consistent_board() -> [u8; 81] {
     #[rustfmt::skip]
-        let consistent = super::tokenize([
+    let consistent = [
         1, 2, 3, 4, 5, 6, 7, 8, 9,
         4, 5, 6, 7, 8, 9, 1, 2, 3,
         7, 8, 9, 1, 2, 3, 4, 5, 6,
@@ -276,13 +416,30 @@ pub fn consistent_board() -> super::Board {
         3, 4, 5, 6, 7, 8, 9, 1, 2,
         6, 7, 8, 9, 1, 2, 3, 4, 5,
         9, 1, 2, 3, 4, 5, 6, 7, 8,
-    ]);
+    ];
     consistent
 }
 
+#[cfg(test)]
+pub fn hard_board() -> [u8; 81] {
+    #[rustfmt::skip]
+    let hard = [
+        8, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 3, 6, 0, 0, 0, 0, 0,
+        0, 7, 0, 0, 9, 0, 2, 0, 0,
+        0, 5, 0, 0, 0, 7, 0, 0, 0,
+        0, 0, 0, 0, 4, 5, 7, 0, 0,
+        0, 0, 0, 1, 0, 0, 0, 3, 0,
+        0, 0, 1, 0, 0, 0, 0, 6, 8,
+        0, 0, 8, 5, 0, 0, 0, 1, 0,
+        0, 9, 0, 0, 0, 0, 4, 0, 0,
+    ];
+    hard
+}
+
 #[cfg(test)]
 pub fn assert_consistent(game: &Game) {
-    for cell in crate::index::BoardIndexer::new() {
+    for cell in crate::index::BoardIndexer::new(game.size) {
         assert!(consistent(game, cell, game.get(cell)));
     }
 }
@@ -291,7 +448,7 @@ pub fn assert_consistent(game: &Game) {
 mod tests {
     use crate::index::BoardIndexer;
 
-    use super::{Cell, Game, Token};
+    use super::{Cell, Difficulty, Game, Size, Symmetry, Token};
 
     #[test]
     fn full_consistency() {
@@ -353,22 +510,343 @@ mod tests {
         assert!(!super::consistent(&jig, Cell::new(4, 4), Token::Two));
     }
 
+    #[test]
+    fn solved() {
+        let mut game = Game::from(super::consistent_board());
+        assert!(super::solved(&game));
+
+        game.set_internal(Cell::new(4, 4), Token::None);
+        assert!(!super::solved(&game));
+
+        game.set_internal(Cell::new(4, 4), Token::One);
+        assert!(!super::solved(&game));
+    }
+
+    #[test]
+    fn count_solutions() {
+        let mut game = Game::from(super::consistent_board());
+        assert_eq!(super::count_solutions(&game, 2), 1);
+
+        for cell in crate::index::RowIndexer::new(Size::STANDARD, 0) {
+            game.set_internal(cell, Token::None);
+        }
+        assert_eq!(super::count_solutions(&game, 2), 1);
+        assert_eq!(super::count_solutions(&game, 0), 0);
+
+        let empty = Game::new_empty(Size::STANDARD);
+        assert_eq!(super::count_solutions(&empty, 1), 1);
+        assert_eq!(super::count_solutions(&empty, 2), 2);
+        assert_eq!(super::count_solutions(&empty, 10), 10);
+    }
+
+    #[test]
+    fn solutions() {
+        let reference = Game::from(super::consistent_board());
+        let mut game = reference.clone();
+        for cell in crate::index::SectorIndexer::new(Size::STANDARD, 4) {
+            game.set_internal(cell, Token::None);
+        }
+
+        let solutions = super::solutions(&game, 2);
+        assert_eq!(solutions.len(), 1);
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(solutions[0].get(cell), reference.get(cell));
+        }
+
+        for solution in super::solutions(&Game::new_empty(Size::STANDARD), 3) {
+            super::assert_consistent(&solution);
+            assert!(super::solved(&solution));
+        }
+    }
+
+    #[test]
+    fn minimal() {
+        let solved = Game::from(super::consistent_board());
+        assert!(!super::minimal(&solved));
+        assert!(!super::minimal(&Game::new_empty(Size::STANDARD)));
+
+        let hard = Game::from(super::hard_board());
+        assert!(super::minimal(&hard));
+
+        let mut extra = hard.clone();
+        let solution = &super::solutions(&hard, 1)[0];
+        let cell = BoardIndexer::new(Size::STANDARD)
+            .find(|cell| hard.get(*cell) == Token::None)
+            .unwrap();
+        extra.set_internal(cell, solution.get(cell));
+        assert!(!super::minimal(&extra));
+    }
+
+    #[test]
+    fn seeded() {
+        use rand::SeedableRng;
+
+        let generate = || {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+            let solved = super::generate_solved(Size::STANDARD, &mut rng);
+            let puzzle =
+                super::prune(&solved, Difficulty::Beginner, Symmetry::None, &mut rng).unwrap();
+            (solved, puzzle)
+        };
+
+        let (solved, puzzle) = generate();
+        let (other_solved, other_puzzle) = generate();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(solved.get(cell), other_solved.get(cell));
+            assert_eq!(puzzle.get(cell), other_puzzle.get(cell));
+        }
+    }
+
+    #[test]
+    fn symmetric_prune() {
+        let solved = Game::from(super::consistent_board());
+        let symmetry = Symmetry::Rot90;
+        let puzzle = super::prune(
+            &solved,
+            Difficulty::Beginner,
+            symmetry,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+
+        assert_eq!(super::count_solutions(&puzzle, 2), 1);
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            let empty = puzzle.get(cell) == Token::None;
+            for other in symmetry.orbit(Size::STANDARD, cell) {
+                assert_eq!(puzzle.get(other) == Token::None, empty);
+            }
+        }
+    }
+
+    #[test]
+    fn prune() {
+        use rand::SeedableRng;
+
+        let solved = Game::from(super::consistent_board());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(99);
+        for difficulty in &[Difficulty::Beginner, Difficulty::Easy, Difficulty::Medium] {
+            let mut pruned = 0;
+            for _ in 0..8 {
+                if let Some(puzzle) = super::prune(&solved, *difficulty, Symmetry::None, &mut rng) {
+                    assert_eq!(puzzle.rate(), *difficulty);
+                    assert_eq!(super::count_solutions(&puzzle, 2), 1);
+                    pruned += 1;
+                }
+            }
+            assert!(pruned > 0, "No puzzle pruned to {:?}", difficulty);
+        }
+
+        // No clue can be removed without losing uniqueness or leaving the beginner band
+        let puzzle = super::prune(
+            &solved,
+            Difficulty::Beginner,
+            Symmetry::None,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(puzzle.is_given(cell), puzzle.get(cell) != Token::None);
+            if puzzle.get(cell) != Token::None {
+                let mut pruned = puzzle.clone();
+                pruned.set_internal(cell, Token::None);
+                assert!(
+                    super::count_solutions(&pruned, 2) > 1 || pruned.rate() > Difficulty::Beginner
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn count_solutions_unsolvable() {
+        #[rustfmt::skip]
+        let clashing = Game::from([
+            1, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(super::count_solutions(&clashing, 2), 0);
+
+        #[rustfmt::skip]
+        let blocked = Game::from([
+            0, 2, 3, 4, 5, 6, 7, 8, 9,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(super::count_solutions(&blocked, 2), 0);
+    }
+
     #[test]
     fn solve() {
         let mut game = Game::from(super::consistent_board());
-        for cell in super::random_sequence().iter().take(10).map(Cell::from) {
+        for cell in super::random_sequence(Size::STANDARD, &mut rand::thread_rng())
+            .into_iter()
+            .take(10)
+        {
             game.set_internal(cell, Token::None);
         }
 
-        let solved = super::solve(&game, false, true);
+        let solved = super::solve(&game, &mut rand::thread_rng());
         assert!(solved.is_some());
 
         let solved = solved.unwrap();
-        for cell in BoardIndexer::new() {
+        for cell in BoardIndexer::new(Size::STANDARD) {
             assert_ne!(solved.get(cell), Token::None);
             assert!(super::consistent(&game, cell, game.get(cell)));
         }
     }
+
+    #[test]
+    fn solve_hard() {
+        #[rustfmt::skip]
+        let expected = Game::from([
+            8, 1, 2, 7, 5, 3, 6, 4, 9,
+            9, 4, 3, 6, 8, 2, 1, 7, 5,
+            6, 7, 5, 4, 9, 1, 2, 8, 3,
+            1, 5, 4, 2, 3, 7, 8, 9, 6,
+            3, 6, 9, 8, 4, 5, 7, 2, 1,
+            2, 8, 7, 1, 6, 9, 5, 3, 4,
+            5, 2, 1, 9, 7, 4, 3, 6, 8,
+            4, 3, 8, 5, 2, 6, 9, 1, 7,
+            7, 9, 6, 3, 1, 8, 4, 5, 2,
+        ]);
+
+        let game = Game::from(super::hard_board());
+        assert_eq!(super::count_solutions(&game, 2), 1);
+
+        let solved = super::solve(&game, &mut rand::thread_rng()).unwrap();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            assert_eq!(solved.get(cell), expected.get(cell));
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        let mut rng = rand::thread_rng();
+        for (rows, columns) in &[(2, 2), (2, 3), (3, 2), (2, 4), (3, 4), (4, 4)] {
+            let size = Size::new(*rows, *columns).unwrap();
+            let solved = super::generate_solved(size, &mut rng);
+            assert!(super::solved(&solved));
+            super::assert_consistent(&solved);
+
+            let puzzle =
+                super::prune(&solved, Difficulty::Beginner, Symmetry::None, &mut rng).unwrap();
+            assert!(puzzle.clues() < size.cells());
+            let solutions = super::solutions(&puzzle, 2);
+            assert_eq!(solutions.len(), 1);
+            for cell in BoardIndexer::new(size) {
+                assert_eq!(solutions[0].get(cell), solved.get(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn jigsaw() {
+        let regions =
+            super::super::Regions::new(Size::STANDARD, &super::super::jigsaw_labels()).unwrap();
+        let empty = Game::with_regions(regions.clone(), vec![Token::None; 81]);
+        let mut rng = rand::thread_rng();
+
+        let solved = super::solve(&empty, &mut rng).unwrap();
+        assert!(super::solved(&solved));
+        for region in 0..9 {
+            let mut tokens = regions
+                .cells(region)
+                .iter()
+                .map(|cell| solved.get(*cell))
+                .collect::<Vec<_>>();
+            tokens.sort();
+            assert_eq!(tokens, Size::STANDARD.tokens());
+        }
+
+        let puzzle = super::prune(&solved, Difficulty::Easy, Symmetry::None, &mut rng);
+        if let Some(puzzle) = puzzle {
+            let solutions = super::solutions(&puzzle, 2);
+            assert_eq!(solutions.len(), 1);
+            assert!(super::solved(&solutions[0]));
+        }
+    }
+
+    #[test]
+    fn killer() {
+        use rand::SeedableRng;
+
+        // Seeded, since the search for some cages takes a while without optimizations
+        let mut rng = rand::rngs::StdRng::seed_from_u64(4321);
+        for size in &[Size::new(2, 3).unwrap(), Size::STANDARD] {
+            let solved = super::generate_solved(*size, &mut rng);
+            let puzzle = super::generate_killer(&solved, &mut rng);
+            assert_eq!(puzzle.clues(), 0);
+
+            let solutions = super::solutions(&puzzle, 2);
+            assert_eq!(solutions.len(), 1);
+            assert_eq!(solutions[0].board, solved.board);
+            for cage in puzzle.cages().unwrap().iter() {
+                let sum = cage
+                    .cells()
+                    .iter()
+                    .map(|cell| solved.get(*cell) as usize)
+                    .sum::<usize>();
+                assert_eq!(cage.sum(), sum);
+            }
+        }
+    }
+
+    #[test]
+    fn rectangular_inconsistency() {
+        let size = Size::new(2, 3).unwrap();
+        let mut game = Game::new_empty(size);
+        game.set_internal(Cell::new(0, 0), Token::Six);
+
+        // Same sector, which spans three columns but only two rows
+        assert!(!game.set(Cell::new(1, 2), Token::Six).unwrap());
+        game.set_internal(Cell::new(1, 2), Token::None);
+        assert!(game.set(Cell::new(2, 1), Token::Six).unwrap());
+        assert!(game.set(Cell::new(1, 3), Token::Six).unwrap());
+    }
+
+    #[test]
+    fn propagate() {
+        let mut game = Game::from(super::consistent_board());
+        for cell in crate::index::RowIndexer::new(Size::STANDARD, 0)
+            .chain(crate::index::ColumnIndexer::new(Size::STANDARD, 0))
+        {
+            game.set_internal(cell, Token::None);
+        }
+
+        assert!(super::propagate(&mut game));
+        assert!(super::solved(&game));
+    }
+
+    #[test]
+    fn propagate_contradiction() {
+        // The bottom left sector has nowhere to place a 1
+        #[rustfmt::skip]
+        let mut game = Game::from([
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            2, 3, 4, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 1, 0, 0,
+        ]);
+
+        assert!(!super::propagate(&mut game));
+    }
 }
 
 #[cfg(all(test, nightly))]
@@ -377,7 +855,7 @@ mod benches {
 
     use test::Bencher;
 
-    use super::{Cell, Game, Token};
+    use super::{Game, Size, Token};
 
     #[bench]
     fn consistent(bench: &mut Bencher) {
@@ -390,12 +868,23 @@ mod benches {
     #[bench]
     fn solve(bench: &mut Bencher) {
         let mut game = Game::from(super::consistent_board());
-        for cell in super::random_sequence().iter().take(30).map(Cell::from) {
+        for cell in super::random_sequence(Size::STANDARD, &mut rand::thread_rng())
+            .into_iter()
+            .take(30)
+        {
             game.set_internal(cell, Token::None);
         }
 
         bench.iter(|| {
-            assert!(super::solve(&game, false).is_some());
+            assert!(super::solve(&game, &mut rand::thread_rng()).is_some());
+        });
+    }
+
+    #[bench]
+    fn solve_hard(bench: &mut Bencher) {
+        let game = Game::from(super::hard_board());
+        bench.iter(|| {
+            assert!(super::solve(&game, &mut rand::thread_rng()).is_some());
         });
     }
 }
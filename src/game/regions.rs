@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use super::{Cell, Size};
+use crate::index::{BoardIndexer, SectorIndexer};
+
+// Units that hold each token once besides rows and columns. These are the sectors of the size on
+// regular boards, and any connected groups of cells on jigsaw boards. Shared between the copies of
+// a game, since the solvers clone it a lot
+#[derive(Clone, Eq, PartialEq)]
+pub struct Regions {
+    size: Size,
+    map: Arc<[usize]>,
+    cells: Arc<[Vec<Cell>]>,
+    // Checked on the hot paths of the solvers, so worked out once
+    standard: bool,
+}
+
+impl Regions {
+    pub fn standard(size: Size) -> Self {
+        let cells = (0..size.side())
+            .map(|sector| SectorIndexer::new(size, sector).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        Self {
+            size,
+            map: sectors(size).into(),
+            cells: cells.into(),
+            standard: true,
+        }
+    }
+
+    // Takes a label per cell, in any order. Regions are numbered by their first cell, so the
+    // usual sectors get their usual numbers. Returns `None` unless there are as many regions as
+    // the side of the board, each with that many cells and all of them connected
+    pub fn new(size: Size, labels: &[usize]) -> Option<Self> {
+        if labels.len() != size.cells() {
+            return None;
+        }
+
+        let mut seen = Vec::new();
+        let mut map = Vec::with_capacity(labels.len());
+        for label in labels {
+            let region = seen
+                .iter()
+                .position(|other| other == label)
+                .unwrap_or_else(|| {
+                    seen.push(*label);
+                    seen.len() - 1
+                });
+            map.push(region);
+        }
+        if seen.len() != size.side() {
+            return None;
+        }
+
+        let mut cells = vec![Vec::with_capacity(size.side()); size.side()];
+        for cell in BoardIndexer::new(size) {
+            cells[map[size.index(cell)]].push(cell);
+        }
+
+        let regions = Self {
+            size,
+            standard: map == sectors(size),
+            map: map.into(),
+            cells: cells.into(),
+        };
+        if regions
+            .cells
+            .iter()
+            .all(|cells| cells.len() == size.side() && components(size, cells).len() == 1)
+        {
+            Some(regions)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub fn region(&self, cell: Cell) -> usize {
+        self.map[self.size.index(cell)]
+    }
+
+    #[inline]
+    pub fn cells(&self, region: usize) -> &[Cell] {
+        &self.cells[region]
+    }
+
+    #[inline]
+    pub fn is_standard(&self) -> bool {
+        self.standard
+    }
+
+    // Labels as taken by `new`, to be moved around along with the board
+    pub fn labels(&self) -> Vec<usize> {
+        self.map.to_vec()
+    }
+}
+
+// The sector of each cell, which numbers the sectors by their first cell as `new` does
+fn sectors(size: Size) -> Vec<usize> {
+    BoardIndexer::new(size)
+        .map(|cell| size.sector(cell))
+        .collect()
+}
+
+// Cells on the board above, below, left and right of the given one
+pub fn neighbours(size: Size, cell: Cell) -> impl Iterator<Item = Cell> {
+    let (row, column) = (cell.row(), cell.column());
+    IntoIterator::into_iter([
+        row.checked_sub(1).map(|row| Cell::new(row, column)),
+        Some(Cell::new(row + 1, column)),
+        column.checked_sub(1).map(|column| Cell::new(row, column)),
+        Some(Cell::new(row, column + 1)),
+    ])
+    .flatten()
+    .filter(move |neighbour| size.contains(*neighbour))
+}
+
+// Splits the cells into groups that are connected through their sides
+pub fn components(size: Size, cells: &[Cell]) -> Vec<Vec<Cell>> {
+    let mut components: Vec<Vec<Cell>> = Vec::new();
+    for cell in cells {
+        if components.iter().any(|component| component.contains(cell)) {
+            continue;
+        }
+
+        let mut reached = vec![*cell];
+        let mut index = 0;
+        while index < reached.len() {
+            let cell = reached[index];
+            index += 1;
+            for neighbour in neighbours(size, cell) {
+                if cells.contains(&neighbour) && !reached.contains(&neighbour) {
+                    reached.push(neighbour);
+                }
+            }
+        }
+        components.push(reached);
+    }
+    components
+}
+
+// One character per cell, the same way tokens are written
+impl std::fmt::Display for Regions {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for region in self.map.iter() {
+            let label = std::convert::TryFrom::try_from(*region)
+                .ok()
+                .and_then(|region| std::char::from_digit(region, 36))
+                .unwrap_or('?');
+            write!(fmt, "{}", label.to_ascii_uppercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Regions {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{:?}: {self}", self.size)
+    }
+}
+
+#[cfg(test)]
+pub fn jigsaw_labels() -> [usize; 81] {
+    #[rustfmt::skip]
+    let labels = [
+        0,0,0,1,1,1,2,2,2,
+        0,3,0,1,1,2,2,4,2,
+        0,3,0,0,1,1,1,4,2,
+        3,3,5,0,1,5,5,4,2,
+        3,5,5,5,5,5,4,4,2,
+        3,3,3,6,5,7,7,4,4,
+        6,3,6,6,7,7,7,7,4,
+        6,6,6,6,8,8,8,7,4,
+        6,8,8,8,8,8,8,7,7,
+    ];
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regions;
+    use crate::game::{Cell, Size};
+
+    #[test]
+    fn standard() {
+        let regions = Regions::standard(Size::STANDARD);
+        assert!(regions.is_standard());
+        assert_eq!(regions.region(Cell::new(4, 7)), 5);
+        assert_eq!(regions.cells(5)[0], Cell::new(3, 6));
+
+        let relabeled = Regions::new(
+            Size::STANDARD,
+            &regions
+                .labels()
+                .iter()
+                .map(|label| 8 - label)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(relabeled, Some(regions));
+    }
+
+    #[test]
+    fn jigsaw() {
+        let regions = Regions::new(Size::STANDARD, &super::jigsaw_labels()).unwrap();
+        assert!(!regions.is_standard());
+        assert_eq!(regions.region(Cell::new(4, 0)), 3);
+        assert_eq!(regions.region(Cell::new(3, 2)), 5);
+        assert_eq!(regions.cells(4).len(), 9);
+        assert_eq!(
+            regions.to_string(),
+            super::jigsaw_labels()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<String>()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut labels = super::jigsaw_labels();
+        assert!(Regions::new(Size::STANDARD, &labels[1..]).is_none());
+
+        // Uneven regions
+        labels[0] = 1;
+        assert!(Regions::new(Size::STANDARD, &labels).is_none());
+
+        // Even, but the last region is split in two
+        let mut labels = super::jigsaw_labels();
+        labels.swap(8 * 9 + 8, 7 * 9 + 2);
+        assert!(Regions::new(Size::STANDARD, &labels).is_none());
+    }
+}
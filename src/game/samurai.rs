@@ -0,0 +1,498 @@
+use super::{ops, Candidates, Cell, Difficulty, Game, Size, Token, JUNCTIONS};
+use crate::error::Error;
+use crate::index::BoardIndexer;
+
+// Rows and columns of the board that holds all five grids
+pub const SIDE: usize = 21;
+
+// Top left cell of each grid, in reading order, so the centre one is in the middle
+const ORIGINS: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+
+// Five standard games, where the centre one shares a corner sector with each of the others. Each
+// grid keeps its own rows, columns and sectors, and the shared cells are set in both grids at once.
+// Cells are addressed on the board that holds them all
+#[derive(Clone)]
+pub struct Samurai {
+    grids: Vec<Game>,
+}
+
+impl Samurai {
+    pub fn new_empty() -> Self {
+        Self {
+            grids: vec![Game::new_empty(Size::STANDARD); ORIGINS.len()],
+        }
+    }
+
+    pub fn new_solved(rng: &mut impl rand::Rng) -> Self {
+        Self::new_empty()
+            .solve(rng)
+            .expect("An empty board is always solvable")
+    }
+
+    // One token per cell of the board, in reading order and skipping the gaps between the grids.
+    // Returns `None` if the count does not match or a token does not fit a standard grid
+    pub fn with_tokens(board: &[Token]) -> Option<Self> {
+        if board.len() != Self::cells().count()
+            || board
+                .iter()
+                .any(|token| *token as usize > Size::STANDARD.side())
+        {
+            return None;
+        }
+
+        let mut samurai = Self::new_empty();
+        for (cell, token) in Self::cells().zip(board) {
+            samurai.set_internal(cell, *token);
+        }
+        Some(samurai.with_givens())
+    }
+
+    // As for a single game, in every grid
+    #[must_use]
+    pub fn with_givens(self) -> Self {
+        Self {
+            grids: self.grids.into_iter().map(Game::with_givens).collect(),
+        }
+    }
+
+    // Cells of the board that belong to a grid, in reading order
+    pub fn cells() -> impl Iterator<Item = Cell> {
+        (0..SIDE * SIDE)
+            .map(|index| Cell::new(index / SIDE, index % SIDE))
+            .filter(|cell| Self::contains(*cell))
+    }
+
+    pub fn contains(cell: Cell) -> bool {
+        Self::locate(cell).next().is_some()
+    }
+
+    // The grids that hold the cell, along with where it is in each of them
+    fn locate(cell: Cell) -> impl Iterator<Item = (usize, Cell)> {
+        let side = Size::STANDARD.side();
+        ORIGINS
+            .iter()
+            .enumerate()
+            .filter_map(move |(grid, (top, left))| {
+                let row = cell.row().checked_sub(*top)?;
+                let column = cell.column().checked_sub(*left)?;
+                (row < side && column < side).then(|| (grid, Cell::new(row, column)))
+            })
+    }
+
+    // Where a cell of a grid is on the board
+    fn place_of(grid: usize, cell: Cell) -> Cell {
+        let (top, left) = ORIGINS[grid];
+        Cell::new(top + cell.row(), left + cell.column())
+    }
+
+    // Cells held by two grids, as they are in each of them
+    fn shared() -> impl Iterator<Item = ((usize, Cell), (usize, Cell))> {
+        Self::cells().filter_map(|cell| {
+            let mut located = Self::locate(cell);
+            Some((located.next()?, located.next()?))
+        })
+    }
+
+    // Top left, top right, centre, bottom left and bottom right
+    #[inline]
+    pub fn grids(&self) -> &[Game] {
+        &self.grids
+    }
+
+    pub fn get(&self, cell: Cell) -> Token {
+        Self::locate(cell)
+            .next()
+            .map_or(Token::None, |(grid, cell)| self.grids[grid].get(cell))
+    }
+
+    pub fn is_given(&self, cell: Cell) -> bool {
+        Self::locate(cell)
+            .next()
+            .is_some_and(|(grid, cell)| self.grids[grid].is_given(cell))
+    }
+
+    pub fn set(&mut self, cell: Cell, token: Token) -> Result<bool, Error> {
+        if self.is_given(cell) {
+            return error!("{cell} is a given");
+        }
+
+        self.set_internal(cell, token);
+        Ok(Self::locate(cell).all(|(grid, cell)| ops::consistent(&self.grids[grid], cell, token)))
+    }
+
+    fn set_internal(&mut self, cell: Cell, token: Token) {
+        for (grid, cell) in Self::locate(cell) {
+            self.grids[grid].set_internal(cell, token);
+        }
+    }
+
+    // Shared cells only keep what both of their grids allow
+    pub fn candidates(&self, cell: Cell) -> Candidates {
+        let mut located = Self::locate(cell);
+        match located.next() {
+            Some((grid, first)) => located.fold(
+                self.grids[grid].candidates(first),
+                |candidates, (grid, cell)| candidates & self.grids[grid].candidates(cell),
+            ),
+            None => Candidates::empty(),
+        }
+    }
+
+    pub fn clues(&self) -> usize {
+        Self::cells()
+            .filter(|cell| self.get(*cell) != Token::None)
+            .count()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.grids.iter().all(Game::is_solved)
+    }
+
+    pub fn solve(&self, rng: &mut impl rand::Rng) -> Option<Self> {
+        use rand::seq::SliceRandom;
+
+        let mut solutions = Vec::with_capacity(1);
+        search(self, 1, &mut |tokens| tokens.shuffle(rng), &mut solutions);
+        solutions.pop()
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solutions(limit).len()
+    }
+
+    pub fn solutions(&self, limit: usize) -> Vec<Self> {
+        let mut solutions = Vec::new();
+        search(self, limit, &mut |_| {}, &mut solutions);
+        solutions
+    }
+
+    // Solves each grid logically in turn and shares what it places with the others, until none of
+    // them gets any further. Puzzles that this does not finish are rated as diabolical, as with a
+    // single grid
+    pub fn rate(&self) -> Difficulty {
+        let mut samurai = self.clone();
+        let mut hardest = Difficulty::Beginner;
+        loop {
+            let mut progress = false;
+            for grid in 0..samurai.grids.len() {
+                let trace = samurai.grids[grid].solve_logically();
+                if let Some(technique) = trace.hardest() {
+                    hardest = hardest.max(technique.difficulty());
+                }
+                for cell in BoardIndexer::new(Size::STANDARD) {
+                    let token = trace.game.get(cell);
+                    if token != Token::None && samurai.grids[grid].get(cell) == Token::None {
+                        samurai.set_internal(Self::place_of(grid, cell), token);
+                        progress = true;
+                    }
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        if samurai.is_solved() {
+            hardest
+        } else {
+            Difficulty::Diabolical
+        }
+    }
+
+    // Removes clues in a random order, as for a single grid. Returns `None` if the result does not
+    // land in the requested band
+    pub fn prune(&self, difficulty: Difficulty, rng: &mut impl rand::Rng) -> Option<Self> {
+        use rand::seq::SliceRandom;
+
+        let mut cells = Self::cells()
+            .filter(|cell| self.get(*cell) != Token::None)
+            .collect::<Vec<_>>();
+        cells.shuffle(rng);
+
+        let mut current = self.clone();
+        for cell in cells {
+            let mut pruned = current.clone();
+            pruned.set_internal(cell, Token::None);
+            let rating = pruned.rate();
+            if rating <= difficulty
+                && (rating < Difficulty::Diabolical || pruned.count_solutions(2) == 1)
+            {
+                current = pruned;
+            }
+        }
+
+        if current.rate() == difficulty {
+            Some(current.with_givens())
+        } else {
+            None
+        }
+    }
+}
+
+fn search(
+    samurai: &Samurai,
+    limit: usize,
+    order: &mut dyn FnMut(&mut [Token]),
+    solutions: &mut Vec<Samurai>,
+) {
+    if limit == 0 || !samurai.grids.iter().all(ops::valid) {
+        return;
+    }
+
+    solve_depth(samurai.clone(), limit, order, solutions);
+}
+
+fn solve_depth(
+    mut samurai: Samurai,
+    limit: usize,
+    order: &mut dyn FnMut(&mut [Token]),
+    solutions: &mut Vec<Samurai>,
+) {
+    if !propagate(&mut samurai) {
+        return;
+    }
+
+    let Some(cell) = most_constrained(&samurai) else {
+        solutions.push(samurai);
+        return;
+    };
+
+    let mut tokens = samurai.candidates(cell).iter().collect::<Vec<_>>();
+    order(&mut tokens);
+
+    for token in &tokens {
+        let mut branch = samurai.clone();
+        branch.set_internal(cell, *token);
+        solve_depth(branch, limit, order, solutions);
+        if solutions.len() >= limit {
+            return;
+        }
+    }
+}
+
+// Propagates singles within each grid, and then carries what they placed in the shared sectors
+// over to the other grid, until neither places anything. Shared cells also get their own naked
+// singles, since each grid only sees half of what rules them out. Returns false on a contradiction
+fn propagate(samurai: &mut Samurai) -> bool {
+    loop {
+        for grid in &mut samurai.grids {
+            if !ops::propagate(grid) {
+                return false;
+            }
+        }
+
+        let mut progress = false;
+        for ((first, first_cell), (second, second_cell)) in Samurai::shared() {
+            let token = samurai.grids[first].get(first_cell);
+            let other = samurai.grids[second].get(second_cell);
+            if token == other {
+                if token == Token::None {
+                    let candidates = samurai.grids[first].candidates(first_cell)
+                        & samurai.grids[second].candidates(second_cell);
+                    if candidates.is_empty() {
+                        return false;
+                    }
+                    if let Some(token) = candidates.single() {
+                        samurai.grids[first].set_internal(first_cell, token);
+                        samurai.grids[second].set_internal(second_cell, token);
+                        progress = true;
+                    }
+                }
+            } else if token == Token::None || other == Token::None {
+                let (grid, cell, token) = if token == Token::None {
+                    (first, first_cell, other)
+                } else {
+                    (second, second_cell, token)
+                };
+                if !samurai.grids[grid].candidates(cell).contains(token) {
+                    return false;
+                }
+                samurai.grids[grid].set_internal(cell, token);
+                progress = true;
+            } else {
+                return false;
+            }
+        }
+
+        if !progress {
+            return true;
+        }
+    }
+}
+
+fn most_constrained(samurai: &Samurai) -> Option<Cell> {
+    let mut most_constrained = None;
+    let mut fewest = usize::MAX;
+
+    for cell in Samurai::cells() {
+        if samurai.get(cell) != Token::None {
+            continue;
+        }
+
+        let candidates = samurai.candidates(cell).len();
+        if candidates < fewest {
+            fewest = candidates;
+            most_constrained = Some(cell);
+            if candidates <= 2 {
+                break;
+            }
+        }
+    }
+
+    most_constrained
+}
+
+// The grids drawn as one, with heavy lines around them and light lines between the sectors
+impl std::fmt::Display for Samurai {
+    // Allowed because it is more readable
+    #[allow(clippy::non_ascii_literal)]
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = Size::STANDARD.sector_columns();
+        let sectors = SIDE / width;
+        // Whether there is a sector at the given row and column of sectors. They are shifted by
+        // one, so that the outside above and to the left can be asked for too
+        let sector = |row: usize, column: usize| {
+            row > 0
+                && column > 0
+                && row <= sectors
+                && column <= sectors
+                && Samurai::contains(Cell::new((row - 1) * width, (column - 1) * width))
+        };
+        let weight = |first: bool, second: bool| {
+            if first == second {
+                usize::from(first)
+            } else {
+                2
+            }
+        };
+
+        for row in 0..=sectors {
+            let mut line = String::new();
+            for column in 0..=sectors {
+                let up = weight(sector(row, column), sector(row, column + 1));
+                let down = weight(sector(row + 1, column), sector(row + 1, column + 1));
+                let left = weight(sector(row, column), sector(row + 1, column));
+                let right = weight(sector(row, column + 1), sector(row + 1, column + 1));
+                line.push(JUNCTIONS[up * 27 + down * 9 + left * 3 + right]);
+                if column < sectors {
+                    let edge = [' ', '─', '━'][right];
+                    line.extend(std::iter::repeat_n(edge, width * 2 - 1));
+                }
+            }
+            writeln!(fmt, "{}", line.trim_end())?;
+
+            if row == sectors {
+                break;
+            }
+            for cell_row in row * width..(row + 1) * width {
+                let mut line = String::new();
+                for column in 0..=sectors {
+                    let edge = weight(sector(row + 1, column), sector(row + 1, column + 1));
+                    line.push([' ', '│', '┃'][edge]);
+                    if column < sectors {
+                        for cell_column in column * width..(column + 1) * width {
+                            if cell_column > column * width {
+                                line.push(' ');
+                            }
+                            line.push_str(&self.get(Cell::new(cell_row, cell_column)).to_string());
+                        }
+                    }
+                }
+                writeln!(fmt, "{}", line.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Comma separated tokens of the cells in reading order, after a prefix that tells it apart from a
+// single grid
+impl std::fmt::Debug for Samurai {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "Samurai:")?;
+        for (index, cell) in Self::cells().enumerate() {
+            if index > 0 {
+                write!(fmt, ",")?;
+            }
+            write!(fmt, "{:?}", self.get(cell))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Samurai;
+    use crate::game::{Cell, Difficulty, Token};
+    use rand::SeedableRng;
+
+    #[test]
+    fn cells() {
+        assert_eq!(Samurai::cells().count(), 5 * 81 - 4 * 9);
+        assert_eq!(Samurai::shared().count(), 4 * 9);
+        assert!(!Samurai::contains(Cell::new(0, 9)));
+        assert!(Samurai::contains(Cell::new(6, 9)));
+        assert!(!Samurai::contains(Cell::new(20, 9)));
+        assert_eq!(
+            Samurai::locate(Cell::new(7, 13)).collect::<Vec<_>>(),
+            vec![(1, Cell::new(7, 1)), (2, Cell::new(1, 7))]
+        );
+    }
+
+    #[test]
+    fn shared() {
+        let mut samurai = Samurai::new_empty();
+        assert!(samurai.set(Cell::new(14, 8), Token::Four).unwrap());
+        assert_eq!(samurai.grids()[2].get(Cell::new(8, 2)), Token::Four);
+        assert_eq!(samurai.grids()[3].get(Cell::new(2, 8)), Token::Four);
+
+        // Each grid rules out what its own peers hold
+        assert!(samurai.set(Cell::new(12, 1), Token::Five).unwrap());
+        assert!(samurai.set(Cell::new(6, 6), Token::Six).unwrap());
+        let candidates = samurai.candidates(Cell::new(12, 6));
+        assert!(!candidates.contains(Token::Four));
+        assert!(!candidates.contains(Token::Five));
+        assert!(!candidates.contains(Token::Six));
+        assert!(samurai.candidates(Cell::new(12, 12)).contains(Token::Five));
+        assert!(!samurai.set(Cell::new(12, 7), Token::Five).unwrap());
+        assert!(samurai.candidates(Cell::new(0, 10)).is_empty());
+    }
+
+    #[test]
+    fn solve() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(17);
+        let solved = Samurai::new_solved(&mut rng);
+        assert!(solved.is_solved());
+        assert_eq!(solved.clues(), Samurai::cells().count());
+
+        let puzzle = solved.prune(Difficulty::Easy, &mut rng).unwrap();
+        assert_eq!(puzzle.rate(), Difficulty::Easy);
+        let solutions = puzzle.solutions(2);
+        assert_eq!(solutions.len(), 1);
+        for cell in Samurai::cells() {
+            assert_eq!(solutions[0].get(cell), solved.get(cell));
+        }
+
+        let tokens = Samurai::cells()
+            .map(|cell| puzzle.get(cell))
+            .collect::<Vec<_>>();
+        let copy = Samurai::with_tokens(&tokens).unwrap();
+        assert_eq!(format!("{copy:?}"), format!("{puzzle:?}"));
+        assert!(Samurai::with_tokens(&tokens[1..]).is_none());
+    }
+
+    #[test]
+    fn display() {
+        let mut samurai = Samurai::new_empty();
+        samurai.set_internal(Cell::new(8, 8), Token::Nine);
+        let display = samurai.to_string();
+        let lines = display.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 29);
+        assert_eq!(lines[0], "┏━━━━━┯━━━━━┯━━━━━┓     ┏━━━━━┯━━━━━┯━━━━━┓");
+        assert_eq!(lines[8], "┠─────┼─────┼─────╄━━━━━╃─────┼─────┼─────┨");
+        assert_eq!(lines[11], "┃     │     │    9│     │     │     │     ┃");
+        assert_eq!(lines[12], "┗━━━━━┷━━━━━╅─────┼─────┼─────╆━━━━━┷━━━━━┛");
+        assert_eq!(lines[13], "            ┃     │     │     ┃");
+    }
+}
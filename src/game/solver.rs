@@ -0,0 +1,216 @@
+use super::{dlx, ops, Game};
+
+pub trait Solver {
+    fn solutions(&self, game: &Game, limit: usize) -> Vec<Game>;
+
+    fn count_solutions(&self, game: &Game, limit: usize) -> usize {
+        self.solutions(game, limit).len()
+    }
+
+    fn solve(&self, game: &Game) -> Option<Game> {
+        self.solutions(game, 1).pop()
+    }
+}
+
+pub struct Backtracking;
+
+impl Solver for Backtracking {
+    fn solutions(&self, game: &Game, limit: usize) -> Vec<Game> {
+        ops::solutions(game, limit)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Backtracking,
+    DancingLinks,
+}
+
+impl Backend {
+    pub fn solver(self) -> &'static dyn Solver {
+        match self {
+            Backend::Backtracking => &Backtracking,
+            Backend::DancingLinks => &dlx::DancingLinks,
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn boards() -> Vec<Game> {
+    include_str!("../../boards.txt")
+        .lines()
+        .filter_map(|line| line.find('[').map(|start| &line[start..]))
+        .map(|line| {
+            let mut board = [0; 81];
+            for (index, digit) in line.bytes().filter(u8::is_ascii_digit).enumerate() {
+                board[index] = digit - b'0';
+            }
+            Game::from(board)
+        })
+        .collect()
+}
+
+// Boards from `boards.txt` that are already solved get a third of their cells kept as clues
+#[cfg(test)]
+pub fn puzzles() -> Vec<Game> {
+    boards()
+        .into_iter()
+        .map(|mut game| {
+            if ops::solved(&game) {
+                for cell in crate::index::BoardIndexer::new(game.size()) {
+                    if (cell.row() + cell.column() * 4) % 3 != 0 {
+                        game.set_internal(cell, super::Token::None);
+                    }
+                }
+            }
+            game
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Backend;
+    use crate::game::{ops, Game, Size};
+    use crate::index::BoardIndexer;
+
+    #[test]
+    fn backends_agree() {
+        for puzzle in super::puzzles() {
+            let backtracking = Backend::Backtracking.solver().solutions(&puzzle, 3);
+            let dancing_links = Backend::DancingLinks.solver().solutions(&puzzle, 3);
+            assert_eq!(backtracking.len(), dancing_links.len());
+
+            for solution in backtracking.iter().chain(&dancing_links) {
+                assert!(ops::solved(solution));
+                for cell in BoardIndexer::new(puzzle.size()) {
+                    let given = puzzle.get(cell);
+                    if given != crate::game::Token::None {
+                        assert_eq!(solution.get(cell), given);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve() {
+        for backend in &[Backend::Backtracking, Backend::DancingLinks] {
+            for board in super::boards() {
+                let solved = backend.solver().solve(&board).unwrap();
+                assert!(ops::solved(&solved));
+                for cell in BoardIndexer::new(board.size()) {
+                    let given = board.get(cell);
+                    if given != crate::game::Token::None {
+                        assert_eq!(solved.get(cell), given);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jigsaw() {
+        let regions =
+            crate::game::Regions::new(Size::STANDARD, &crate::game::jigsaw_labels()).unwrap();
+        let mut puzzle = Game::with_regions(regions, vec![crate::game::Token::None; 81])
+            .solve(&mut rand::thread_rng())
+            .unwrap();
+        for cell in BoardIndexer::new(Size::STANDARD) {
+            if (cell.row() * 2 + cell.column()) % 3 != 0 {
+                puzzle.set_internal(cell, crate::game::Token::None);
+            }
+        }
+
+        let backtracking = Backend::Backtracking.solver().solutions(&puzzle, 10);
+        let dancing_links = Backend::DancingLinks.solver().solutions(&puzzle, 10);
+        assert!(!backtracking.is_empty());
+        assert_eq!(backtracking.len(), dancing_links.len());
+        for solution in backtracking.iter().chain(&dancing_links) {
+            assert!(ops::solved(solution));
+        }
+    }
+
+    #[test]
+    fn killer() {
+        use rand::SeedableRng;
+
+        // Seeded, since the search for some cages takes a while without optimizations
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let solved = Game::new_solved(Size::STANDARD, &mut rng);
+        let puzzle = solved.killer(&mut rng);
+
+        for backend in &[Backend::Backtracking, Backend::DancingLinks] {
+            let solutions = backend.solver().solutions(&puzzle, 2);
+            assert_eq!(solutions.len(), 1);
+            for cell in BoardIndexer::new(Size::STANDARD) {
+                assert_eq!(solutions[0].get(cell), solved.get(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        let mut rng = rand::thread_rng();
+        for size in &[Size::new(2, 2).unwrap(), Size::new(2, 3).unwrap()] {
+            let mut puzzle = Game::new_solved(*size, &mut rng);
+            for cell in BoardIndexer::new(*size) {
+                if (cell.row() + cell.column() * 3) % 2 == 0 {
+                    puzzle.set_internal(cell, crate::game::Token::None);
+                }
+            }
+
+            let backtracking = Backend::Backtracking.solver().count_solutions(&puzzle, 10);
+            let dancing_links = Backend::DancingLinks.solver().count_solutions(&puzzle, 10);
+            assert!(backtracking > 0);
+            assert_eq!(backtracking, dancing_links);
+        }
+    }
+}
+
+#[cfg(all(test, nightly))]
+mod benches {
+    extern crate test;
+
+    use test::Bencher;
+
+    use super::Backend;
+
+    fn bench_backend(bench: &mut Bencher, backend: Backend) {
+        let puzzles = super::puzzles();
+        bench.iter(|| {
+            for puzzle in &puzzles {
+                assert!(backend.solver().solve(puzzle).is_some());
+            }
+        });
+    }
+
+    fn bench_uniqueness(bench: &mut Bencher, backend: Backend) {
+        let puzzles = super::puzzles();
+        bench.iter(|| {
+            for puzzle in &puzzles {
+                assert!(backend.solver().count_solutions(puzzle, 2) > 0);
+            }
+        });
+    }
+
+    #[bench]
+    fn backtracking(bench: &mut Bencher) {
+        bench_backend(bench, Backend::Backtracking);
+    }
+
+    #[bench]
+    fn dancing_links(bench: &mut Bencher) {
+        bench_backend(bench, Backend::DancingLinks);
+    }
+
+    #[bench]
+    fn backtracking_uniqueness(bench: &mut Bencher) {
+        bench_uniqueness(bench, Backend::Backtracking);
+    }
+
+    #[bench]
+    fn dancing_links_uniqueness(bench: &mut Bencher) {
+        bench_uniqueness(bench, Backend::DancingLinks);
+    }
+}
@@ -1,104 +1,333 @@
-use super::{Board, Token};
-use crate::index::{ColumnIndexer, RowIndexer};
+use super::{Cell, Size, Token};
+use crate::error::Error;
+use crate::index::BoardIndexer;
+
+// Rebuilds the board so that each cell takes the token found at the cell given by `source`
+fn remap<T: Copy>(board: &mut [T], size: Size, source: impl Fn(Cell) -> Cell) {
+    let other = board.to_vec();
+    for cell in BoardIndexer::new(size) {
+        board[size.index(cell)] = other[size.index(source(cell))];
+    }
+}
+
+pub fn rotate<T: Copy>(board: &mut [T], size: Size) {
+    remap(board, size, |cell| Cell::new(cell.column(), cell.row()));
+}
+
+pub fn mirror_columns<T: Copy>(board: &mut [T], size: Size) {
+    let last = size.side() - 1;
+    remap(board, size, |cell| {
+        Cell::new(cell.row(), last - cell.column())
+    });
+}
+
+pub fn mirror_rows<T: Copy>(board: &mut [T], size: Size) {
+    let last = size.side() - 1;
+    remap(board, size, |cell| {
+        Cell::new(last - cell.row(), cell.column())
+    });
+}
+
+// A symmetry of the board: the tokens are relabelled, the rows are moved within and between bands,
+// the columns within and between stacks, and the board is transposed last if asked to. Transposing
+// keeps the sectors only when they are square, so otherwise the lines must keep to the shape of a
+// sector both ways, as the mirrors do
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transformation {
+    size: Size,
+    // Image of each token, starting from the first one
+    tokens: Vec<Token>,
+    // Row and column each line moves to
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    transpose: bool,
+}
 
-pub fn shift(board: &mut Board, amount: u8) {
-    if amount == 0 {
-        return;
+impl Transformation {
+    pub fn identity(size: Size) -> Self {
+        Self {
+            size,
+            tokens: size.tokens().to_vec(),
+            rows: (0..size.side()).collect(),
+            columns: (0..size.side()).collect(),
+            transpose: false,
+        }
     }
 
-    let effective_amount = (amount - 1) % 8;
-    for token in board.iter_mut() {
-        if token != &Token::None {
-            *token = Token::from(((*token as u8 + effective_amount) % 9) + 1);
+    pub fn new(
+        size: Size,
+        tokens: Vec<Token>,
+        rows: Vec<usize>,
+        columns: Vec<usize>,
+        transpose: bool,
+    ) -> Result<Self, Error> {
+        let (height, width) = (size.sector_rows(), size.sector_columns());
+        let indices = tokens
+            .iter()
+            .map(|token| (*token as usize).wrapping_sub(1))
+            .collect::<Vec<_>>();
+        if !is_permutation(&indices, size.side()) {
+            return error!("the tokens must be a permutation of those of a {size} board");
+        }
+        if !is_permutation(&rows, size.side()) || !keeps_blocks(&rows, height) {
+            return error!("the rows must be a permutation that keeps the bands together");
+        }
+        if !is_permutation(&columns, size.side()) || !keeps_blocks(&columns, width) {
+            return error!("the columns must be a permutation that keeps the stacks together");
         }
+        // Standard sectors would end up on their side, which `Game::transform` refuses. Jigsaw
+        // boards of the size can still be transposed, since their regions only have to stay
+        // connected, so the lines only have to fit the sectors both ways
+        if transpose
+            && height != width
+            && !(keeps_blocks(&rows, width) && keeps_blocks(&columns, height))
+        {
+            return error!("transposing {size} sectors needs lines that fit both sector sides");
+        }
+
+        Ok(Self {
+            size,
+            tokens,
+            rows,
+            columns,
+            transpose,
+        })
     }
-}
 
-pub fn rotate(board: &mut Board) {
-    let other = *board;
-    for i in 0..9 {
-        let col = ColumnIndexer::new(i);
-        let row = RowIndexer::new(i);
-        for (col, row) in col.zip(row) {
-            board[col.index()] = other[row.index()];
+    // Any of the symmetries of the board with standard sectors, all as likely
+    pub fn random(size: Size, rng: &mut impl rand::Rng) -> Self {
+        Self {
+            size,
+            tokens: permutation(size.side(), 1, rng)
+                .into_iter()
+                .map(|index| size.tokens()[index])
+                .collect(),
+            rows: permutation(size.side(), size.sector_rows(), rng),
+            columns: permutation(size.side(), size.sector_columns(), rng),
+            transpose: size.is_square() && rng.gen(),
         }
     }
-}
 
-pub fn mirror_columns(board: &mut Board) {
-    let other = *board;
-    for i in 0..9 {
-        let first = ColumnIndexer::new(i);
-        let second = ColumnIndexer::new(8 - i);
-        for (first, second) in first.zip(second) {
-            board[first.index()] = other[second.index()];
+    // Relabels the tokens at random and leaves the cells where they are
+    pub fn random_tokens(size: Size, rng: &mut impl rand::Rng) -> Self {
+        Self {
+            tokens: Self::random(size, rng).tokens,
+            ..Self::identity(size)
         }
     }
-}
 
-pub fn mirror_rows(board: &mut Board) {
-    let other = *board;
-    for i in 0..9 {
-        let first = RowIndexer::new(i);
-        let second = RowIndexer::new(8 - i);
-        for (first, second) in first.zip(second) {
-            board[first.index()] = other[second.index()];
+    pub fn transpose(size: Size) -> Self {
+        Self {
+            transpose: true,
+            ..Self::identity(size)
         }
     }
-}
 
-pub fn swap_columns(board: &mut Board, sector_column: usize, pivot: usize) {
-    let other = *board;
-    let col1 = ((pivot + 1) % 3) + (sector_column % 3) * 3;
-    let col2 = ((pivot + 2) % 3) + (sector_column % 3) * 3;
+    pub fn mirror_rows(size: Size) -> Self {
+        Self {
+            rows: (0..size.side()).rev().collect(),
+            ..Self::identity(size)
+        }
+    }
 
-    let first = ColumnIndexer::new(col1);
-    let second = ColumnIndexer::new(col2);
-    for (first, second) in first.zip(second) {
-        board[first.index()] = other[second.index()];
-        board[second.index()] = other[first.index()];
+    pub fn mirror_columns(size: Size) -> Self {
+        Self {
+            columns: (0..size.side()).rev().collect(),
+            ..Self::identity(size)
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
     }
-}
 
-pub fn swap_rows(board: &mut Board, sector_row: usize, pivot: usize) {
-    let other = *board;
-    let row1 = (((pivot + 1) % 3) + (sector_row % 3) * 3) * 9;
-    let row2 = (((pivot + 2) % 3) + (sector_row % 3) * 3) * 9;
+    #[inline]
+    pub fn is_transposed(&self) -> bool {
+        self.transpose
+    }
 
-    board[row1..(9 + row1)].copy_from_slice(&other[row2..(9 + row2)]);
-    board[row2..(9 + row2)].copy_from_slice(&other[row1..(9 + row1)]);
-}
+    pub fn token(&self, token: Token) -> Token {
+        match token {
+            Token::None => Token::None,
+            _ => self.tokens[token as usize - 1],
+        }
+    }
 
-pub fn swap_column_sector(board: &mut Board, pivot: usize) {
-    let other = *board;
-    let col1 = ((pivot + 1) % 3) * 3;
-    let col2 = ((pivot + 2) % 3) * 3;
+    // Where the token of the cell ends up
+    pub fn cell(&self, cell: Cell) -> Cell {
+        let (row, column) = (self.rows[cell.row()], self.columns[cell.column()]);
+        if self.transpose {
+            Cell::new(column, row)
+        } else {
+            Cell::new(row, column)
+        }
+    }
+
+    // This transformation followed by the other one
+    pub fn then(&self, other: &Self) -> Self {
+        let compose =
+            |first: &[usize], second: &[usize]| first.iter().map(|line| second[*line]).collect();
+
+        // A transpose in between turns the rows of the other transformation into columns
+        let (rows, columns) = if self.transpose {
+            (other.columns.as_slice(), other.rows.as_slice())
+        } else {
+            (other.rows.as_slice(), other.columns.as_slice())
+        };
+        Self {
+            size: self.size,
+            tokens: self
+                .tokens
+                .iter()
+                .map(|token| other.token(*token))
+                .collect(),
+            rows: compose(&self.rows, rows),
+            columns: compose(&self.columns, columns),
+            transpose: self.transpose != other.transpose,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        let invert = |lines: &[usize]| {
+            let mut inverse = vec![0; lines.len()];
+            for (line, image) in lines.iter().enumerate() {
+                inverse[*image] = line;
+            }
+            inverse
+        };
+
+        let mut tokens = vec![Token::None; self.tokens.len()];
+        for (index, token) in self.tokens.iter().enumerate() {
+            tokens[*token as usize - 1] = self.size.tokens()[index];
+        }
+        let (rows, columns) = (invert(&self.rows), invert(&self.columns));
+        let (rows, columns) = if self.transpose {
+            (columns, rows)
+        } else {
+            (rows, columns)
+        };
+        Self {
+            size: self.size,
+            tokens,
+            rows,
+            columns,
+            transpose: self.transpose,
+        }
+    }
 
-    let first = ColumnIndexer::new(col1);
-    let second = ColumnIndexer::new(col2);
-    for (first, second) in first.zip(second) {
-        let first = first.index();
-        let second = second.index();
-        board[first..(3 + first)].copy_from_slice(&other[second..(3 + second)]);
-        board[second..(3 + second)].copy_from_slice(&other[first..(3 + first)]);
+    // Lines that stay in place or are only mirrored, which keep the diagonals and the windows of the
+    // variants
+    pub fn is_dihedral(&self) -> bool {
+        let last = self.size.side() - 1;
+        let mirrored = |lines: &[usize]| {
+            lines.iter().enumerate().all(|(line, image)| *image == line)
+                || lines
+                    .iter()
+                    .enumerate()
+                    .all(|(line, image)| *image == last - line)
+        };
+        mirrored(&self.rows) && mirrored(&self.columns)
+    }
+
+    // Moves the cells and relabels their tokens
+    pub fn apply(&self, board: &mut [Token]) {
+        self.permute(board);
+        for token in board.iter_mut() {
+            *token = self.token(*token);
+        }
+    }
+
+    // Only moves the cells, for anything else kept per cell such as region labels
+    pub fn permute<T: Copy>(&self, board: &mut [T]) {
+        let other = board.to_vec();
+        for cell in BoardIndexer::new(self.size) {
+            board[self.size.index(self.cell(cell))] = other[self.size.index(cell)];
+        }
     }
 }
 
-pub fn swap_row_sector(board: &mut Board, pivot: usize) {
-    let other = *board;
-    let row1 = (((pivot + 1) % 3) * 3) * 9;
-    let row2 = (((pivot + 2) % 3) * 3) * 9;
+// The sector shape, the image of each token, then the row and the column each line moves to,
+// counting from 1, all separated by colons and followed by "T" when transposed. Read back by
+// `options::to_transformation`
+impl std::fmt::Display for Transformation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |values: Vec<String>| values.join(",");
+        let lines =
+            |lines: &[usize]| list(lines.iter().map(|line| (line + 1).to_string()).collect());
+        write!(
+            fmt,
+            "{}x{}:{}:{}:{}",
+            self.size.sector_rows(),
+            self.size.sector_columns(),
+            list(
+                self.tokens
+                    .iter()
+                    .map(|token| format!("{token:?}"))
+                    .collect()
+            ),
+            lines(&self.rows),
+            lines(&self.columns)
+        )?;
+        if self.transpose {
+            write!(fmt, ":T")?;
+        }
+        Ok(())
+    }
+}
 
-    board[row1..(27 + row1)].copy_from_slice(&other[row2..(27 + row2)]);
-    board[row2..(27 + row2)].copy_from_slice(&other[row1..(27 + row1)]);
+fn is_permutation(values: &[usize], count: usize) -> bool {
+    let mut seen = vec![false; count];
+    values.len() == count
+        && values
+            .iter()
+            .all(|value| *value < count && !std::mem::replace(&mut seen[*value], true))
+}
+
+// Whether the lines in each block of the given length move together to the same block
+fn keeps_blocks(lines: &[usize], length: usize) -> bool {
+    lines
+        .chunks(length)
+        .all(|block| block.iter().all(|line| line / length == block[0] / length))
+}
+
+// Shuffles the blocks of the given length and the values within each of them
+fn permutation(count: usize, length: usize, rng: &mut impl rand::Rng) -> Vec<usize> {
+    use rand::seq::SliceRandom;
+
+    let mut blocks = (0..count / length).collect::<Vec<_>>();
+    blocks.shuffle(rng);
+    let mut permutation = Vec::with_capacity(count);
+    for block in blocks {
+        let mut lines = (block * length..(block + 1) * length).collect::<Vec<_>>();
+        lines.shuffle(rng);
+        permutation.extend(lines);
+    }
+    permutation
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::tokenize;
+    use rand::SeedableRng;
+
+    use super::Transformation;
+    use crate::game::{Cell, Game, Size, Token};
+
+    fn tokenize(board: [u8; 81]) -> Vec<Token> {
+        board.iter().map(Token::from).collect()
+    }
+
+    fn lines(size: Size, rows: &[usize], columns: &[usize]) -> Transformation {
+        let tokens = size.tokens().to_vec();
+        Transformation::new(size, tokens, rows.to_vec(), columns.to_vec(), false).unwrap()
+    }
+
+    fn identity() -> Vec<usize> {
+        (0..9).collect()
+    }
 
     #[test]
-    fn shift() {
+    fn tokens() {
         #[rustfmt::skip]
         let mut jig  = tokenize([
             0,1,2,3,4,5,6,7,8,
@@ -125,7 +354,13 @@ mod tests {
             4,5,6,7,8,9,1,2,0,
             ]);
 
-        super::shift(&mut jig, 2);
+        let tokens = [3, 4, 5, 6, 7, 8, 9, 1, 2]
+            .iter()
+            .map(Token::from)
+            .collect();
+        Transformation::new(Size::STANDARD, tokens, identity(), identity(), false)
+            .unwrap()
+            .apply(&mut jig);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
@@ -159,7 +394,7 @@ mod tests {
             0,1,2,3,4,5,6,7,8,
             ]);
 
-        super::rotate(&mut jig);
+        super::rotate(&mut jig, Size::STANDARD);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
@@ -193,7 +428,7 @@ mod tests {
             0,1,2,3,4,5,6,7,8,
             ]);
 
-        super::mirror_columns(&mut jig);
+        super::mirror_columns(&mut jig, Size::STANDARD);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
@@ -227,14 +462,14 @@ mod tests {
             0,0,0,0,0,0,0,0,0,
             ]);
 
-        super::mirror_rows(&mut jig);
+        super::mirror_rows(&mut jig, Size::STANDARD);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
     }
 
     #[test]
-    fn swap_columns() {
+    fn columns() {
         #[rustfmt::skip]
         let mut jig  = tokenize([
             0,1,2,3,4,5,6,7,8,
@@ -261,14 +496,14 @@ mod tests {
             2,3,4,7,6,5,8,9,0,
             ]);
 
-        super::swap_columns(&mut jig, 1, 1);
+        lines(Size::STANDARD, &identity(), &[0, 1, 2, 5, 4, 3, 6, 7, 8]).apply(&mut jig);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
     }
 
     #[test]
-    fn swap_rows() {
+    fn rows() {
         #[rustfmt::skip]
         let mut jig  = tokenize([
             0,1,2,3,4,5,6,7,8,
@@ -295,14 +530,14 @@ mod tests {
             2,3,4,5,6,7,8,9,0,
             ]);
 
-        super::swap_rows(&mut jig, 0, 0);
+        lines(Size::STANDARD, &[0, 2, 1, 3, 4, 5, 6, 7, 8], &identity()).apply(&mut jig);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
     }
 
     #[test]
-    fn swap_column_sector() {
+    fn stacks() {
         #[rustfmt::skip]
         let mut jig  = tokenize([
             0,1,2,3,4,5,6,7,8,
@@ -329,14 +564,14 @@ mod tests {
             8,9,0,5,6,7,2,3,4,
             ]);
 
-        super::swap_column_sector(&mut jig, 1);
+        lines(Size::STANDARD, &identity(), &[6, 7, 8, 3, 4, 5, 0, 1, 2]).apply(&mut jig);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
     }
 
     #[test]
-    fn swap_row_sector() {
+    fn bands() {
         #[rustfmt::skip]
         let mut jig  = tokenize([
             0,1,2,3,4,5,6,7,8,
@@ -363,9 +598,127 @@ mod tests {
             5,6,7,8,9,0,1,2,3,
             ]);
 
-        super::swap_row_sector(&mut jig, 0);
+        lines(Size::STANDARD, &[0, 1, 2, 6, 7, 8, 3, 4, 5], &identity()).apply(&mut jig);
         for i in 0..81 {
             assert_eq!(jig[i], expected[i]);
         }
     }
+
+    #[test]
+    fn rectangular() {
+        let size = Size::new(2, 3).unwrap();
+        let mut jig = (0..36)
+            .map(|index: u8| Token::from(index % 6 + 1))
+            .collect::<Vec<_>>();
+
+        let rows = (0..6).collect::<Vec<_>>();
+        lines(size, &rows, &[3, 4, 5, 0, 1, 2]).apply(&mut jig);
+        assert_eq!(
+            jig[..6],
+            [4, 5, 6, 1, 2, 3]
+                .iter()
+                .map(Token::from)
+                .collect::<Vec<_>>()[..]
+        );
+
+        lines(size, &rows, &[0, 1, 2, 3, 5, 4]).apply(&mut jig);
+        assert_eq!(
+            jig[..6],
+            [4, 5, 6, 1, 3, 2]
+                .iter()
+                .map(Token::from)
+                .collect::<Vec<_>>()[..]
+        );
+
+        let tokens = [2, 3, 4, 5, 6, 1].iter().map(Token::from).collect();
+        Transformation::new(size, tokens, rows.clone(), rows, false)
+            .unwrap()
+            .apply(&mut jig);
+        assert_eq!(
+            jig[..6],
+            [5, 6, 1, 2, 4, 3]
+                .iter()
+                .map(Token::from)
+                .collect::<Vec<_>>()[..]
+        );
+    }
+
+    #[test]
+    fn inverse() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let board = Game::new_solved(Size::STANDARD, &mut rng).board;
+        for _ in 0..8 {
+            let transformation = Transformation::random(Size::STANDARD, &mut rng);
+            let mut other = board.clone();
+            transformation.apply(&mut other);
+            transformation.inverse().apply(&mut other);
+            assert_eq!(other, board);
+
+            let identity = transformation.then(&transformation.inverse());
+            assert_eq!(identity, Transformation::identity(Size::STANDARD));
+        }
+    }
+
+    #[test]
+    fn then() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        let size = Size::STANDARD;
+        let board = Game::new_solved(size, &mut rng).board;
+        let (first, second) = (
+            Transformation::random(size, &mut rng).then(&Transformation::transpose(size)),
+            Transformation::random(size, &mut rng),
+        );
+
+        let mut other = board.clone();
+        first.apply(&mut other);
+        second.apply(&mut other);
+        let mut composed = board.clone();
+        first.then(&second).apply(&mut composed);
+        assert_eq!(composed, other);
+
+        let cell = Cell::new(1, 7);
+        assert_eq!(
+            first.then(&second).cell(cell),
+            second.cell(first.cell(cell))
+        );
+        assert_eq!(
+            first.then(&second).token(Token::Four),
+            second.token(first.token(Token::Four))
+        );
+    }
+
+    #[test]
+    fn display() {
+        let size = Size::new(2, 2).unwrap();
+        let transformation =
+            Transformation::mirror_rows(size).then(&Transformation::transpose(size));
+        assert_eq!(transformation.to_string(), "2x2:1,2,3,4:4,3,2,1:1,2,3,4:T");
+    }
+
+    #[test]
+    fn invalid() {
+        let size = Size::new(2, 3).unwrap();
+        let lines = (0..6).collect::<Vec<_>>();
+        let tokens = size.tokens().to_vec();
+        let new = |tokens: &[Token], rows: &[usize], columns: &[usize], transpose| {
+            Transformation::new(
+                size,
+                tokens.to_vec(),
+                rows.to_vec(),
+                columns.to_vec(),
+                transpose,
+            )
+        };
+
+        assert!(new(&tokens, &lines, &lines, false).is_ok());
+        assert!(new(&tokens[1..], &lines, &lines, false).is_err());
+        assert!(new(&[Token::One; 6], &lines, &lines, false).is_err());
+        // Rows 1 and 2 are in different bands
+        assert!(new(&tokens, &[0, 2, 1, 3, 4, 5], &lines, false).is_err());
+        assert!(new(&tokens, &lines, &[0, 1, 2, 3, 4, 4], false).is_err());
+
+        // The sectors are not square, but mirrors fit them both ways
+        assert!(new(&tokens, &lines, &[0, 2, 1, 3, 4, 5], true).is_err());
+        assert!(new(&tokens, &[5, 4, 3, 2, 1, 0], &lines, true).is_ok());
+    }
 }
@@ -0,0 +1,262 @@
+use super::{Candidates, Cell, Constraint, Game, Size, Token};
+use crate::index::{DiagonalIndexer, WindowIndexer};
+
+// Rule sets on top of the usual ones. They only depend on where the cells are, so they survive the
+// mirrors and rotations of the board
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Variant {
+    // Both main diagonals hold every token once, as in X-Sudoku
+    Diagonal,
+    // So do the windows between the sectors, as in Windoku or Hyper Sudoku
+    Windoku,
+    // Cells a chess knight's move apart never hold the same token
+    AntiKnight,
+    // Neither do cells a king's move apart, which only adds the diagonal neighbours
+    AntiKing,
+}
+
+const KNIGHT: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const KING: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl Variant {
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Diagonal => "X-Sudoku",
+            Variant::Windoku => "Windoku",
+            Variant::AntiKnight => "Anti-knight",
+            Variant::AntiKing => "Anti-king",
+        }
+    }
+
+    pub fn fits(self, size: Size) -> bool {
+        match self {
+            Variant::Diagonal | Variant::AntiKnight | Variant::AntiKing => true,
+            Variant::Windoku => size.is_square(),
+        }
+    }
+
+    // Tokens that the variant keeps out of the cell, which are the ones in the units that hold it
+    // or a move away
+    fn mask(self, game: &Game, cell: Cell) -> u32 {
+        let size = game.size();
+        let unit = |cells: &mut dyn Iterator<Item = Cell>| {
+            cells
+                .filter(|other| *other != cell)
+                .fold(0, |mask, other| mask | game.get(other).mask())
+        };
+
+        match self {
+            Variant::Diagonal => {
+                let mut mask = 0;
+                if cell.row() == cell.column() {
+                    mask |= unit(&mut DiagonalIndexer::new(size, 0));
+                }
+                if cell.row() + cell.column() == size.side() - 1 {
+                    mask |= unit(&mut DiagonalIndexer::new(size, 1));
+                }
+                mask
+            }
+            Variant::Windoku => {
+                window(size, cell).map_or(0, |window| unit(&mut WindowIndexer::new(size, window)))
+            }
+            Variant::AntiKnight => unit(&mut moves(size, cell, &KNIGHT)),
+            Variant::AntiKing => unit(&mut moves(size, cell, &KING)),
+        }
+    }
+}
+
+// Cells on the board that the offsets lead to from the given one
+fn moves(size: Size, cell: Cell, offsets: &'static [(isize, isize)]) -> impl Iterator<Item = Cell> {
+    offsets
+        .iter()
+        .filter_map(move |(rows, columns)| {
+            Some(Cell::new(
+                cell.row().checked_add_signed(*rows)?,
+                cell.column().checked_add_signed(*columns)?,
+            ))
+        })
+        .filter(move |other| size.contains(*other))
+}
+
+// The window that holds the cell, if any. Windows start one cell in and leave a cell between them
+fn window(size: Size, cell: Cell) -> Option<usize> {
+    let width = size.sector_columns();
+    let offset = |line: usize| {
+        let line = line.checked_sub(1)?;
+        let window = line / (width + 1);
+        (line % (width + 1) < width && window < width - 1).then_some(window)
+    };
+    Some(offset(cell.row())? * (width - 1) + offset(cell.column())?)
+}
+
+impl Constraint for Variant {
+    fn name(&self) -> &'static str {
+        Variant::name(*self)
+    }
+
+    fn allows(&self, game: &Game, cell: Cell, token: Token) -> bool {
+        self.mask(game, cell) & token.mask() == 0
+    }
+
+    fn eliminate(&self, game: &Game, cell: Cell, candidates: Candidates) -> Candidates {
+        candidates & !Candidates::from_mask(self.mask(game, cell))
+    }
+
+    fn units(&self, size: Size) -> Vec<Vec<Cell>> {
+        match self {
+            Variant::Diagonal => (0..2)
+                .map(|diagonal| DiagonalIndexer::new(size, diagonal).collect())
+                .collect(),
+            Variant::Windoku => (0..WindowIndexer::windows(size))
+                .map(|window| WindowIndexer::new(size, window).collect())
+                .collect(),
+            Variant::AntiKnight | Variant::AntiKing => Vec::new(),
+        }
+    }
+
+    fn unit_name(&self, unit: usize) -> String {
+        match (self, unit) {
+            (Variant::Diagonal, 0) => "the main diagonal".to_string(),
+            (Variant::Diagonal, _) => "the anti-diagonal".to_string(),
+            _ => format!("window {}", unit + 1),
+        }
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variant;
+    use crate::game::{ops, Backend, Cell, Constraint, Game, Size, Token};
+    use crate::index::BoardIndexer;
+    use rand::SeedableRng;
+
+    #[test]
+    fn window() {
+        let size = Size::STANDARD;
+        assert_eq!(super::window(size, Cell::new(0, 1)), None);
+        assert_eq!(super::window(size, Cell::new(1, 1)), Some(0));
+        assert_eq!(super::window(size, Cell::new(3, 7)), Some(1));
+        assert_eq!(super::window(size, Cell::new(4, 2)), None);
+        assert_eq!(super::window(size, Cell::new(7, 2)), Some(2));
+        assert_eq!(super::window(size, Cell::new(5, 5)), Some(3));
+        assert_eq!(super::window(size, Cell::new(5, 8)), None);
+
+        let units = Variant::Windoku.units(size);
+        for cell in BoardIndexer::new(size) {
+            let holding = units.iter().position(|unit| unit.contains(&cell));
+            assert_eq!(super::window(size, cell), holding);
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let mut game = Game::new_empty(Size::STANDARD)
+            .with_variant(Variant::Diagonal)
+            .with_variant(Variant::Windoku);
+        assert!(game.set(Cell::new(0, 8), Token::Three).unwrap());
+        assert!(!game.set(Cell::new(8, 0), Token::Three).unwrap());
+        assert!(game.set(Cell::new(8, 0), Token::Four).unwrap());
+        assert!(!game.candidates(Cell::new(4, 4)).contains(Token::Three));
+        assert!(game.candidates(Cell::new(4, 3)).contains(Token::Three));
+
+        assert!(game.set(Cell::new(1, 1), Token::Five).unwrap());
+        assert!(!game.set(Cell::new(3, 3), Token::Five).unwrap());
+        assert!(game.set(Cell::new(3, 3), Token::None).unwrap());
+        assert!(!game.candidates(Cell::new(2, 3)).contains(Token::Five));
+        assert!(game.candidates(Cell::new(4, 3)).contains(Token::Five));
+    }
+
+    #[test]
+    fn moves() {
+        let mut game = Game::new_empty(Size::STANDARD)
+            .with_variant(Variant::AntiKnight)
+            .with_variant(Variant::AntiKing);
+        assert!(game.set(Cell::new(4, 4), Token::One).unwrap());
+        assert!(!game.set(Cell::new(6, 5), Token::One).unwrap());
+        assert!(!ops::consistent(&game, Cell::new(6, 5), Token::One));
+        assert!(game.set(Cell::new(6, 5), Token::Two).unwrap());
+        assert!(!game.set(Cell::new(5, 3), Token::Two).unwrap());
+        assert!(game.set(Cell::new(5, 3), Token::None).unwrap());
+
+        assert!(!game.candidates(Cell::new(3, 3)).contains(Token::One));
+        assert!(!game.candidates(Cell::new(2, 3)).contains(Token::One));
+        assert!(game.candidates(Cell::new(1, 3)).contains(Token::One));
+        assert_eq!(
+            super::moves(Size::STANDARD, Cell::new(0, 0), &super::KNIGHT).count(),
+            2
+        );
+        assert_eq!(
+            super::moves(Size::STANDARD, Cell::new(8, 4), &super::KING).count(),
+            5
+        );
+    }
+
+    #[test]
+    fn solve() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(15);
+        for variants in &[
+            &[Variant::Diagonal][..],
+            &[Variant::Windoku],
+            &[Variant::Diagonal, Variant::Windoku],
+            &[Variant::AntiKnight],
+            &[Variant::AntiKing, Variant::Diagonal],
+        ] {
+            let template = variants
+                .iter()
+                .fold(Game::new_empty(Size::STANDARD), |game, variant| {
+                    game.with_variant(*variant)
+                });
+            let solved = template.solve(&mut rng).unwrap();
+            assert!(solved.is_solved());
+            for variant in *variants {
+                for unit in variant.units(Size::STANDARD) {
+                    let mut tokens = unit
+                        .iter()
+                        .map(|cell| solved.get(*cell))
+                        .collect::<Vec<_>>();
+                    tokens.sort();
+                    assert_eq!(tokens, Size::STANDARD.tokens());
+                }
+            }
+
+            // Both backends must keep the rules on a puzzle with a few solutions
+            let mut puzzle = solved.clone();
+            for cell in BoardIndexer::new(Size::STANDARD) {
+                if (cell.row() * 4 + cell.column()) % 3 != 0 {
+                    puzzle.set_internal(cell, Token::None);
+                }
+            }
+            let backtracking = Backend::Backtracking.solver().solutions(&puzzle, 10);
+            let dancing_links = Backend::DancingLinks.solver().solutions(&puzzle, 10);
+            assert_eq!(backtracking.len(), dancing_links.len());
+            for solution in backtracking.iter().chain(&dancing_links) {
+                assert!(solution.is_solved());
+            }
+        }
+    }
+}
@@ -1,17 +1,18 @@
-use crate::game::Cell;
+use crate::game::{Cell, Size};
 
 pub struct RowIndexer {
-    index: usize,
+    row: usize,
+    column: usize,
     end: usize,
 }
 
 impl RowIndexer {
-    pub fn new(row: usize) -> Self {
-        assert!(row < 9, "Row index out of bounds: {}", row);
-        let index = row * 9;
+    pub fn new(size: Size, row: usize) -> Self {
+        assert!(row < size.side(), "Row index out of bounds: {}", row);
         Self {
-            index,
-            end: index + 9,
+            row,
+            column: 0,
+            end: size.side(),
         }
     }
 }
@@ -19,9 +20,9 @@ impl RowIndexer {
 impl std::iter::Iterator for RowIndexer {
     type Item = Cell;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.end {
-            let cell = Some(Cell::from(self.index));
-            self.index += 1;
+        if self.column < self.end {
+            let cell = Some(Cell::new(self.row, self.column));
+            self.column += 1;
             cell
         } else {
             None
@@ -30,22 +31,32 @@ impl std::iter::Iterator for RowIndexer {
 }
 
 pub struct ColumnIndexer {
-    index: usize,
+    row: usize,
+    column: usize,
+    end: usize,
 }
 
 impl ColumnIndexer {
-    pub fn new(column: usize) -> Self {
-        assert!(column < 9, "Column index out of bounds: {}", column);
-        Self { index: column }
+    pub fn new(size: Size, column: usize) -> Self {
+        assert!(
+            column < size.side(),
+            "Column index out of bounds: {}",
+            column
+        );
+        Self {
+            row: 0,
+            column,
+            end: size.side(),
+        }
     }
 }
 
 impl std::iter::Iterator for ColumnIndexer {
     type Item = Cell;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < 81 {
-            let cell = Some(Cell::from(self.index));
-            self.index += 9;
+        if self.row < self.end {
+            let cell = Some(Cell::new(self.row, self.column));
+            self.row += 1;
             cell
         } else {
             None
@@ -54,19 +65,27 @@ impl std::iter::Iterator for ColumnIndexer {
 }
 
 pub struct SectorIndexer {
+    top: usize,
+    left: usize,
+    width: usize,
     index: usize,
-    wall: usize,
     end: usize,
 }
 
 impl SectorIndexer {
-    pub fn new(sector: usize) -> Self {
-        assert!(sector < 9, "Sector index out of bounds: {}", sector);
-        let index = (sector / 3) * 27 + (sector % 3) * 3;
+    pub fn new(size: Size, sector: usize) -> Self {
+        assert!(
+            sector < size.side(),
+            "Sector index out of bounds: {}",
+            sector
+        );
+        let sectors_per_row = size.side() / size.sector_columns();
         Self {
-            index,
-            wall: index + 3,
-            end: index + 9 + 9 + 3,
+            top: (sector / sectors_per_row) * size.sector_rows(),
+            left: (sector % sectors_per_row) * size.sector_columns(),
+            width: size.sector_columns(),
+            index: 0,
+            end: size.side(),
         }
     }
 }
@@ -75,12 +94,98 @@ impl std::iter::Iterator for SectorIndexer {
     type Item = Cell;
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < self.end {
-            let cell = Some(Cell::from(self.index));
+            let cell = Some(Cell::new(
+                self.top + self.index / self.width,
+                self.left + self.index % self.width,
+            ));
+            self.index += 1;
+            cell
+        } else {
+            None
+        }
+    }
+}
+
+// Diagonal 0 runs from the top left corner to the bottom right one, and diagonal 1 from the top
+// right corner to the bottom left one
+pub struct DiagonalIndexer {
+    anti: bool,
+    index: usize,
+    end: usize,
+}
+
+impl DiagonalIndexer {
+    pub fn new(size: Size, diagonal: usize) -> Self {
+        assert!(diagonal < 2, "Diagonal index out of bounds: {}", diagonal);
+        Self {
+            anti: diagonal == 1,
+            index: 0,
+            end: size.side(),
+        }
+    }
+}
+
+impl std::iter::Iterator for DiagonalIndexer {
+    type Item = Cell;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.end {
+            let column = if self.anti {
+                self.end - 1 - self.index
+            } else {
+                self.index
+            };
+            let cell = Some(Cell::new(self.index, column));
+            self.index += 1;
+            cell
+        } else {
+            None
+        }
+    }
+}
+
+// Windows are sector sized and sit between the sectors, one cell in from the edges and one cell
+// apart, as in Windoku. Only square sectors leave room for them
+pub struct WindowIndexer {
+    top: usize,
+    left: usize,
+    width: usize,
+    index: usize,
+    end: usize,
+}
+
+impl WindowIndexer {
+    pub fn new(size: Size, window: usize) -> Self {
+        assert!(size.is_square(), "Windows need square sectors");
+        assert!(
+            window < Self::windows(size),
+            "Window index out of bounds: {}",
+            window
+        );
+        let width = size.sector_columns();
+        let windows_per_row = width - 1;
+        Self {
+            top: 1 + (window / windows_per_row) * (width + 1),
+            left: 1 + (window % windows_per_row) * (width + 1),
+            width,
+            index: 0,
+            end: size.side(),
+        }
+    }
+
+    pub fn windows(size: Size) -> usize {
+        (size.sector_rows() - 1) * (size.sector_columns() - 1)
+    }
+}
+
+impl std::iter::Iterator for WindowIndexer {
+    type Item = Cell;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.end {
+            let cell = Some(Cell::new(
+                self.top + self.index / self.width,
+                self.left + self.index % self.width,
+            ));
             self.index += 1;
-            if self.index == self.wall {
-                self.index += 6;
-                self.wall += 9;
-            }
             cell
         } else {
             None
@@ -89,20 +194,21 @@ impl std::iter::Iterator for SectorIndexer {
 }
 
 pub struct BoardIndexer {
+    size: Size,
     index: usize,
 }
 
 impl BoardIndexer {
-    pub fn new() -> Self {
-        Self { index: 0 }
+    pub fn new(size: Size) -> Self {
+        Self { size, index: 0 }
     }
 }
 
 impl std::iter::Iterator for BoardIndexer {
     type Item = Cell;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < 81 {
-            let cell = Some(Cell::from(self.index));
+        if self.index < self.size.cells() {
+            let cell = Some(self.size.cell(self.index));
             self.index += 1;
             cell
         } else {
@@ -113,7 +219,10 @@ impl std::iter::Iterator for BoardIndexer {
 
 #[cfg(test)]
 mod tests {
-    use super::{BoardIndexer, ColumnIndexer, RowIndexer, SectorIndexer};
+    use super::{
+        BoardIndexer, ColumnIndexer, DiagonalIndexer, RowIndexer, SectorIndexer, WindowIndexer,
+    };
+    use crate::game::Size;
 
     #[test]
     fn row_low() {
@@ -130,9 +239,9 @@ mod tests {
             0,0,0,0,0,0,0,0,0,
             ];
 
-        let iter = RowIndexer::new(0);
+        let iter = RowIndexer::new(Size::STANDARD, 0);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
@@ -151,9 +260,9 @@ mod tests {
             0,1,2,3,4,5,6,7,8,
             ];
 
-        let iter = RowIndexer::new(8);
+        let iter = RowIndexer::new(Size::STANDARD, 8);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
@@ -172,9 +281,9 @@ mod tests {
             8,0,0,0,0,0,0,0,0,
             ];
 
-        let iter = ColumnIndexer::new(0);
+        let iter = ColumnIndexer::new(Size::STANDARD, 0);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
@@ -193,9 +302,9 @@ mod tests {
             0,0,0,0,0,0,0,0,8,
             ];
 
-        let iter = ColumnIndexer::new(8);
+        let iter = ColumnIndexer::new(Size::STANDARD, 8);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
@@ -214,9 +323,9 @@ mod tests {
             0,0,0,0,0,0,0,0,0,
             ];
 
-        let iter = SectorIndexer::new(0);
+        let iter = SectorIndexer::new(Size::STANDARD, 0);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
@@ -235,16 +344,92 @@ mod tests {
             0,0,0,0,0,0,6,7,8,
             ];
 
-        let iter = SectorIndexer::new(8);
+        let iter = SectorIndexer::new(Size::STANDARD, 8);
         for (index, cell) in iter.enumerate() {
-            assert_eq!(index, jig[cell.index()]);
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
         }
     }
 
     #[test]
     fn board() {
-        for (index, cell) in BoardIndexer::new().enumerate() {
-            assert_eq!(index, cell.index());
+        for (index, cell) in BoardIndexer::new(Size::STANDARD).enumerate() {
+            assert_eq!(index, Size::STANDARD.index(cell));
+        }
+    }
+
+    #[test]
+    fn sector_rectangular() {
+        let size = Size::new(2, 3).unwrap();
+
+        #[rustfmt::skip]
+        let jig: [usize; 36] = [
+            0,0,0,0,0,0,
+            0,0,0,0,0,0,
+            0,0,0,0,0,0,
+            0,0,0,0,0,0,
+            0,0,0,0,1,2,
+            0,0,0,3,4,5,
+            ];
+
+        let iter = SectorIndexer::new(size, 5);
+        for (index, cell) in iter.enumerate() {
+            assert_eq!(index, jig[size.index(cell)]);
+        }
+        assert_eq!(SectorIndexer::new(size, 5).count(), 6);
+    }
+
+    #[test]
+    fn diagonals() {
+        #[rustfmt::skip]
+        let jig: [usize; 81] = [
+            0,0,0,0,0,0,0,0,8,
+            0,0,0,0,0,0,0,7,0,
+            0,0,0,0,0,0,6,0,0,
+            0,0,0,0,0,5,0,0,0,
+            0,0,0,0,4,0,0,0,0,
+            0,0,0,3,0,0,0,0,0,
+            0,0,2,0,0,0,0,0,0,
+            0,1,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,0,0,0,
+            ];
+
+        for (index, cell) in DiagonalIndexer::new(Size::STANDARD, 0).enumerate() {
+            assert_eq!(cell.row(), index);
+            assert_eq!(cell.column(), index);
+        }
+        let iter = DiagonalIndexer::new(Size::STANDARD, 1);
+        for (index, cell) in iter.enumerate() {
+            assert_eq!(8 - index, jig[Size::STANDARD.index(cell)]);
         }
     }
+
+    #[test]
+    fn windows() {
+        #[rustfmt::skip]
+        let jig: [usize; 81] = [
+            0,0,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,0,0,0,
+            0,0,0,0,0,0,1,2,0,
+            0,0,0,0,0,3,4,5,0,
+            0,0,0,0,0,6,7,8,0,
+            0,0,0,0,0,0,0,0,0,
+            ];
+
+        assert_eq!(WindowIndexer::windows(Size::STANDARD), 4);
+        let iter = WindowIndexer::new(Size::STANDARD, 3);
+        let cells = iter.collect::<Vec<_>>();
+        assert_eq!(cells.len(), 9);
+        assert_eq!(cells[0].row(), 5);
+        assert_eq!(cells[0].column(), 5);
+        for (index, cell) in cells.into_iter().enumerate().skip(1) {
+            assert_eq!(index, jig[Size::STANDARD.index(cell)]);
+        }
+
+        let top = WindowIndexer::new(Size::STANDARD, 1).next().unwrap();
+        assert_eq!((top.row(), top.column()), (1, 5));
+        assert_eq!(WindowIndexer::windows(Size::new(2, 2).unwrap()), 1);
+    }
 }
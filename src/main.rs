@@ -8,72 +8,354 @@ mod error;
 mod game;
 mod index;
 mod options;
+mod play;
 
+const ATTEMPTS: usize = 1000;
+
+// Errors are reported on stderr, with a failing exit status for scripts
 fn main() {
     let options = options::parse();
 
-    match options {
-        options::Options::Generate(options) => {
-            let mut solved = options
-                .template()
-                .solve()
-                .expect("The template given is unsolvable");
-
-            let mut i = 0_u16;
-            let mut puzzles = [None; 3];
-            loop {
-                if i.trailing_zeros() >= 4 {
-                    eprintln!("Pruning");
-                    puzzles = solved.prune_per_gaps(options.max_difficulty());
+    let result = match options {
+        options::Options::Generate(options) if options.samurai() => generate_samurai(&options),
+        options::Options::Generate(options) => generate(&options),
+        options::Options::Solve(options) => solve(&options),
+        options::Options::Hint(options) => hint(&options),
+        options::Options::Check(options) => check(&options),
+        options::Options::Transform(options) => transform(&options),
+        options::Options::Play(options) => {
+            let puzzle = options.puzzle().or_else(|| {
+                generate_puzzle(
+                    &options.board(),
+                    options.difficulty(),
+                    game::Symmetry::None,
+                    false,
+                    &mut rand::thread_rng(),
+                )
+                .map(|(_, puzzle)| puzzle)
+            });
+            if let Some(puzzle) = puzzle {
+                match play::play(puzzle) {
+                    Ok(()) => Ok(()),
+                    Err(error) => error!(error; "Could not play"),
                 }
+            } else {
+                error!(
+                    "Could not generate {} puzzles on a {} board",
+                    options.difficulty(),
+                    options.board().size()
+                )
+            }
+        }
+        options::Options::Rate(options) => rate(&options),
+    };
 
-                shuffle_puzzle(&mut solved, &mut puzzles);
-                print_puzzle(&solved, &puzzles);
-
-                if options.count() > 0 {
-                    if i == u16::max_value() {
-                        i = 0;
-                    } else {
-                        i += 1;
-                        if i == options.count() {
-                            break;
-                        }
-                    }
-                }
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+fn solve(options: &options::Solve) -> Result<(), error::Error> {
+    let puzzle = options.puzzle()?;
+
+    match puzzle {
+        options::Puzzle::Single(puzzle) => {
+            if options.explain() {
+                explain(&puzzle);
             }
+            let solutions = options.backend().solver().solutions(&puzzle, 2);
+            print_solutions(&solutions);
+        }
+        // The grids are solved together, so neither the backends nor the explanations apply
+        options::Puzzle::Samurai(puzzle) => print_solutions(&puzzle.solutions(2)),
+    }
+    Ok(())
+}
+
+fn hint(options: &options::Hint) -> Result<(), error::Error> {
+    let puzzle = options.puzzle()?;
+
+    let clashes = puzzle.clashes();
+    if !clashes.is_empty() {
+        println!("Entries breaking a rule: {}", cells(&clashes));
+    } else if puzzle.is_solved() {
+        println!("Puzzle is already solved");
+    } else if let Some(hint) = puzzle.next_hint() {
+        println!("{}", hint.text(options.tier()));
+    } else {
+        println!("No logical step applies. The puzzle might need guessing, or hold a mistake");
+    }
+    Ok(())
+}
+
+fn check(options: &options::Check) -> Result<(), error::Error> {
+    let check = options.puzzle()?.check(options.board())?;
+
+    if check.is_solvable() {
+        println!("No mistakes in the {} entries so far", check.entries());
+        return Ok(());
+    }
+    println!("Wrong entries: {}", cells(check.wrong()));
+    if check.inconsistent().is_empty() {
+        println!("No rule is broken yet, but the board cannot be solved anymore");
+    } else {
+        println!("Entries breaking a rule: {}", cells(check.inconsistent()));
+    }
+    Ok(())
+}
+
+fn cells(cells: &[game::Cell]) -> String {
+    cells
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Prints the transformation along with the board, so that other boards can be moved the same way
+fn transform(options: &options::Transform) -> Result<(), error::Error> {
+    let puzzle = options.puzzle()?;
+    let (board, transformation) = if let Some(transformation) = options.transformation() {
+        (puzzle.transform(&transformation)?, transformation)
+    } else {
+        let seed = options.seed().unwrap_or_else(rand::random);
+        puzzle.shuffle(seed, game::Symmetry::None)
+    };
+
+    println!("Transformation: {transformation}");
+    println!("[{board:?}]");
+    Ok(())
+}
+
+fn print_solutions<T: std::fmt::Display + std::fmt::Debug>(solutions: &[T]) {
+    if let Some(solved) = solutions.first() {
+        if solutions.len() > 1 {
+            println!("Puzzle has multiple solutions. Showing one of them");
         }
-        options::Options::Solve(options) => {
-            if let Some(solved) = options.puzzle().solve() {
-                println!("{}", solved);
-                println!("{:?}", solved);
+        println!("{solved}");
+        println!("{solved:?}");
+    } else {
+        println!("Puzzle is unsolvable");
+    }
+}
+
+fn generate(options: &options::Generate) -> Result<(), error::Error> {
+    use rand::SeedableRng;
+
+    let template = options.template()?;
+
+    let seed = options.seed().unwrap_or_else(rand::random);
+    eprintln!("Seed: {seed}");
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+
+    let mut i = 0_u16;
+    let mut solved = template.clone();
+    let mut puzzle = template.clone();
+    loop {
+        // Shuffling leaves the cages of Killer puzzles alone, so each one is generated anew
+        if i.trailing_zeros() >= 4 || options.killer() {
+            eprintln!("Pruning");
+            let generated = if options.killer() {
+                generate_killer(&template, &mut rng)
             } else {
-                println!("Puzzle is unsolvable");
+                generate_puzzle(
+                    &template,
+                    options.difficulty(),
+                    options.symmetry(),
+                    options.minimal(),
+                    &mut rng,
+                )
+            };
+            let Some(generated) = generated else {
+                return error!(
+                    "Could not generate {} puzzles on a {} board",
+                    options.difficulty(),
+                    solved.size()
+                );
+            };
+            (solved, puzzle) = generated;
+        }
+
+        if options.killer() {
+            println!("{puzzle}");
+            print_puzzle(&solved, &puzzle, puzzle.rate());
+        } else {
+            shuffle_puzzle(&mut solved, &mut puzzle, options.symmetry(), &mut rng);
+            print_puzzle(&solved, &puzzle, options.difficulty());
+        }
+        if options.minimal() {
+            println!("Clues: {}", puzzle.clues());
+        }
+
+        if options.count() > 0 {
+            if i == u16::MAX {
+                i = 0;
+            } else {
+                i += 1;
+                if i == options.count() {
+                    return Ok(());
+                }
             }
         }
-        options::Options::Play(_) => {}
     }
 }
 
-fn shuffle_puzzle(solved: &mut game::Game, puzzles: &mut [Option<game::Game>; 3]) {
-    use rand::Rng;
+// Samurai puzzles are generated anew each time, since there is no shuffling them
+fn generate_samurai(options: &options::Generate) -> Result<(), error::Error> {
+    use rand::SeedableRng;
+
+    let seed = options.seed().unwrap_or_else(rand::random);
+    eprintln!("Seed: {seed}");
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
 
-    let mut rng = rand::thread_rng();
-    let seed = rng.gen::<u64>();
-    *solved = solved.shuffle(seed);
-    for puzzle in puzzles.iter_mut().flatten() {
-        *puzzle = puzzle.shuffle(seed);
+    let mut i = 0_u16;
+    loop {
+        eprintln!("Pruning");
+        let generated = (0..ATTEMPTS).find_map(|_| {
+            let solved = game::Samurai::new_solved(&mut rng);
+            let puzzle = solved.prune(options.difficulty(), &mut rng)?;
+            Some((solved, puzzle))
+        });
+        let Some((solved, puzzle)) = generated else {
+            return error!(
+                "Could not generate {} Samurai puzzles",
+                options.difficulty()
+            );
+        };
+
+        println!("{puzzle}");
+        println!("Solved: [{solved:?}]");
+        println!("{}: [{puzzle:?}]", options.difficulty());
+
+        if options.count() > 0 {
+            i = i.saturating_add(1);
+            if i == options.count() {
+                return Ok(());
+            }
+        }
     }
 }
 
-fn print_puzzle(solved: &game::Game, puzzles: &[Option<game::Game>; 3]) {
-    println!("Solved: [{:?}]", solved);
-    if let Some(easy) = puzzles[0] {
-        println!("Easy:   [{:?}]", easy);
+fn rate(options: &options::Rate) -> Result<(), error::Error> {
+    let fingerprint = options.fingerprint();
+    let result = if let Some(path) = options.file() {
+        std::fs::File::open(path)
+            .and_then(|file| rate_lines(std::io::BufReader::new(file), fingerprint))
+    } else {
+        rate_lines(std::io::stdin().lock(), fingerprint)
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(error) => error!(error; "Could not rate the puzzles"),
     }
-    if let Some(medium) = puzzles[1] {
-        println!("Medium: [{:?}]", medium);
+}
+
+fn rate_lines(input: impl std::io::BufRead, fingerprint: bool) -> std::io::Result<()> {
+    if fingerprint {
+        println!("Clues\tSolutions\tDifficulty\tHardest technique\tFingerprint\tPuzzle");
+    } else {
+        println!("Clues\tSolutions\tDifficulty\tHardest technique\tPuzzle");
     }
-    if let Some(hard) = puzzles[2] {
-        println!("Hard:   [{:?}]", hard);
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+
+        // Blank lines and headings
+        if !line.chars().any(char::is_numeric) {
+            continue;
+        }
+
+        let puzzle = match options::to_game(&line) {
+            Ok(puzzle) => puzzle,
+            Err(error) => {
+                eprintln!("Skipping line {}: {error}", index + 1);
+                continue;
+            }
+        };
+
+        let (solutions, difficulty, hardest) = match puzzle.count_solutions(2) {
+            0 => ("unsolvable", "-", "-"),
+            // Where the techniques get stuck, the hardest one used was not enough
+            1 => {
+                let trace = puzzle.solve_logically();
+                let hardest = if trace.is_solved() {
+                    trace.hardest().map_or("-", |technique| technique.name())
+                } else {
+                    "guessing"
+                };
+                ("unique", trace.difficulty().name(), hardest)
+            }
+            _ => ("multiple", "-", "-"),
+        };
+
+        print!("{}\t{solutions}\t{difficulty}\t{hardest}\t", puzzle.clues());
+        if fingerprint {
+            print!("{:016x}\t", puzzle.fingerprint());
+        }
+        println!("[{puzzle:?}]");
     }
+    Ok(())
+}
+
+fn explain(puzzle: &game::Game) {
+    let trace = puzzle.solve_logically();
+    for (index, step) in trace.steps.iter().enumerate() {
+        println!("{:>3}. {step}", index + 1);
+    }
+
+    if !trace.is_solved() {
+        println!("No further logical steps. Falling back to the solver");
+        println!("{}", trace.game);
+    }
+}
+
+// Shuffling does not change the difficulty, so only pruning needs to be retried. Small boards
+// never need the harder techniques, so the attempts are capped rather than looping forever
+fn generate_puzzle(
+    template: &game::Game,
+    difficulty: game::Difficulty,
+    symmetry: game::Symmetry,
+    minimal: bool,
+    rng: &mut impl rand::Rng,
+) -> Option<(game::Game, game::Game)> {
+    for _ in 0..ATTEMPTS {
+        let solved = template
+            .solve(rng)
+            .expect("The template given is unsolvable");
+        if let Some(puzzle) = solved.prune(difficulty, symmetry, rng) {
+            // Removals can be held back by the difficulty band, so a minimal puzzle might need a few
+            // attempts
+            if !minimal || puzzle.is_minimal() {
+                return Some((solved, puzzle));
+            }
+        }
+    }
+    None
+}
+
+fn generate_killer(
+    template: &game::Game,
+    rng: &mut impl rand::Rng,
+) -> Option<(game::Game, game::Game)> {
+    let solved = template.solve(rng)?;
+    let puzzle = solved.killer(rng);
+    Some((solved, puzzle))
+}
+
+fn shuffle_puzzle(
+    solved: &mut game::Game,
+    puzzle: &mut game::Game,
+    symmetry: game::Symmetry,
+    rng: &mut impl rand::Rng,
+) {
+    let (shuffled, transformation) = solved.shuffle(rng.gen::<u64>(), symmetry);
+    *puzzle = puzzle
+        .transform(&transformation)
+        .expect("The puzzle has the rules of its solution");
+    *solved = shuffled;
+}
+
+fn print_puzzle(solved: &game::Game, puzzle: &game::Game, difficulty: game::Difficulty) {
+    println!("Solved: [{solved:?}]");
+    println!("{difficulty}: [{puzzle:?}]");
 }
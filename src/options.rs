@@ -3,8 +3,6 @@ use clap::Clap;
 use crate::error;
 use crate::game;
 
-static EMPTY_BOARD: &str = "0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0";
-
 pub fn parse() -> Options {
     Options::parse()
 }
@@ -16,81 +14,766 @@ pub enum Options {
     Generate(Generate),
     /// Solve a given puzzle
     Solve(Solve),
+    /// Explain the next logical step of a puzzle
+    Hint(Hint),
+    /// Find the mistakes on a board played from a puzzle
+    Check(Check),
+    /// Shuffle a board, or move it by a transformation printed by an earlier shuffle
+    Transform(Transform),
     /// Play Sudoku
     Play(Play),
+    /// Rate puzzles read one per line
+    Rate(Rate),
 }
 
 #[derive(Clap, Debug)]
 pub struct Generate {
-    /// A base template to work from
-    #[clap(short, long, default_value = EMPTY_BOARD, parse(try_from_str = to_game))]
-    template: game::Game,
-    /// The max difficulty to try for
-    #[clap(short ="d", long, default_value = "m", parse(try_from_str = to_difficulty))]
-    max_difficulty: game::Difficulty,
+    /// A base template to work from (an empty board if not given)
+    #[clap(short, long, parse(try_from_str = to_game))]
+    template: Option<game::Game>,
+    /// Size of the board, as in 6x6 or 12x12:4x3 to pick the sector shape
+    #[clap(long, default_value = "9x9", parse(try_from_str = to_size))]
+    size: game::Size,
+    /// Jigsaw regions for the board, as one label per cell
+    #[clap(short, long, conflicts_with = "template", parse(try_from_str = to_regions))]
+    regions: Option<game::Regions>,
+    /// The difficulty band to generate puzzles in
+    #[clap(short, long, default_value = "m", parse(try_from_str = to_difficulty))]
+    difficulty: game::Difficulty,
     /// Number of puzzles to generate (0 for indefinite)
     #[clap(short, long, default_value = "1")]
     count: u16,
+    /// Seed for the random generator to reproduce a previous run (random if not given)
+    #[clap(short, long)]
+    seed: Option<u64>,
+    /// Symmetry of the clue pattern
+    #[clap(long, default_value = "none", parse(try_from_str = to_symmetry))]
+    symmetry: game::Symmetry,
+    /// Only generate minimal puzzles, where every clue is needed for a unique solution. Symmetric
+    /// clue patterns are rarely minimal, so this cannot be combined with a symmetry
+    #[clap(short, long, conflicts_with = "symmetry")]
+    minimal: bool,
+    /// Generate Killer puzzles, with cages instead of clues. These are rated instead of kept in the
+    /// difficulty band
+    #[clap(short, long)]
+    killer: bool,
+    /// Extra rules the puzzles follow: x-sudoku, windoku, anti-knight or anti-king. Can be given
+    /// more than once
+    #[clap(long, parse(try_from_str = to_variant))]
+    variant: Vec<game::Variant>,
+    /// Generate Samurai puzzles, five standard grids where the centre one shares its corner
+    /// sectors with the others
+    #[clap(long, conflicts_with_all = &["template", "regions", "minimal", "killer", "variant"])]
+    samurai: bool,
 }
 
 #[derive(Clap, Debug)]
 pub struct Solve {
     /// Puzzle to be solved
+    #[clap(short, long, parse(try_from_str = to_puzzle))]
+    puzzle: Puzzle,
+    /// Solver backend to use
+    #[clap(short, long, default_value = "backtracking", parse(try_from_str = to_backend))]
+    backend: game::Backend,
+    /// Explain the logical steps that lead to the solution
+    #[clap(short, long)]
+    explain: bool,
+    /// Extra rules the puzzle follows: x-sudoku, windoku, anti-knight or anti-king. Can be given
+    /// more than once
+    #[clap(long, parse(try_from_str = to_variant))]
+    variant: Vec<game::Variant>,
+}
+
+#[derive(Clap, Debug)]
+pub struct Hint {
+    /// Puzzle to give a hint for, partially filled in
     #[clap(short, long, parse(try_from_str = to_game))]
     puzzle: game::Game,
+    /// How much to give away: nudge, technique or answer
+    #[clap(short, long, default_value = "nudge", parse(try_from_str = to_tier))]
+    tier: game::Tier,
+    /// Extra rules the puzzle follows: x-sudoku, windoku, anti-knight or anti-king. Can be given
+    /// more than once
+    #[clap(long, parse(try_from_str = to_variant))]
+    variant: Vec<game::Variant>,
 }
 
 #[derive(Clap, Debug)]
-pub struct Play {
-    /// Puzzle to play
-    #[clap(short, long, default_value = EMPTY_BOARD, parse(try_from_str = to_game))]
+pub struct Check {
+    /// Puzzle as it was given
+    #[clap(short, long, parse(try_from_str = to_game))]
     puzzle: game::Game,
+    /// Board as the player left it, holding the givens of the puzzle
+    #[clap(short, long, parse(try_from_str = to_board))]
+    board: Box<game::Game>,
+    /// Extra rules the puzzle follows: x-sudoku, windoku, anti-knight or anti-king. Can be given
+    /// more than once
+    #[clap(long, parse(try_from_str = to_variant))]
+    variant: Vec<game::Variant>,
+}
+
+#[derive(Clap, Debug)]
+pub struct Transform {
+    /// Puzzle or board to transform, partially filled in
+    #[clap(short, long, parse(try_from_str = to_game))]
+    puzzle: game::Game,
+    /// Transformation to apply, as printed by a shuffle (a random one if not given)
+    #[clap(short, long, parse(try_from_str = to_transformation))]
+    transformation: Option<game::Transformation>,
+    /// Undo the transformation instead, to map a shuffled board back to the original
+    #[clap(short, long, requires = "transformation")]
+    inverse: bool,
+    /// Seed for the random shuffle to reproduce a previous run (random if not given)
+    #[clap(short, long, conflicts_with = "transformation")]
+    seed: Option<u64>,
+    /// Extra rules the puzzle follows: x-sudoku, windoku, anti-knight or anti-king. Can be given
+    /// more than once
+    #[clap(long, parse(try_from_str = to_variant))]
+    variant: Vec<game::Variant>,
+}
+
+#[derive(Clap, Debug)]
+pub struct Play {
+    /// Puzzle to play (generated if not given)
+    #[clap(short, long, parse(try_from_str = to_game))]
+    puzzle: Option<game::Game>,
+    /// Size of the generated puzzle, as in 6x6 or 12x12:4x3 to pick the sector shape
+    #[clap(long, default_value = "9x9", parse(try_from_str = to_size))]
+    size: game::Size,
+    /// Jigsaw regions for the generated puzzle, as one label per cell
+    #[clap(short, long, conflicts_with = "puzzle", parse(try_from_str = to_regions))]
+    regions: Option<game::Regions>,
     /// Difficulty to play in
     #[clap(short, long, default_value = "m", parse(try_from_str = to_difficulty))]
     difficulty: game::Difficulty,
 }
 
+#[derive(Clap, Debug)]
+pub struct Rate {
+    /// File with one puzzle per line (reads from stdin if not given)
+    #[clap(short, long, parse(from_os_str))]
+    file: Option<std::path::PathBuf>,
+    /// Add a fingerprint that is the same for puzzles that only differ by a shuffle
+    #[clap(long)]
+    fingerprint: bool,
+}
+
+// Puzzles to solve are either a single grid or a Samurai one
+#[derive(Debug)]
+pub enum Puzzle {
+    Single(Box<game::Game>),
+    Samurai(game::Samurai),
+}
+
 impl Generate {
-    pub fn template(&self) -> game::Game {
-        self.template
+    pub fn template(&self) -> Result<game::Game, error::Error> {
+        let template = self
+            .template
+            .clone()
+            .unwrap_or_else(|| empty_board(self.size, self.regions.as_ref()));
+        with_variants(template, &self.variant)
     }
-    pub fn max_difficulty(&self) -> game::Difficulty {
-        self.max_difficulty
+    pub fn difficulty(&self) -> game::Difficulty {
+        self.difficulty
     }
     pub fn count(&self) -> u16 {
         self.count
     }
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    pub fn symmetry(&self) -> game::Symmetry {
+        self.symmetry
+    }
+    pub fn minimal(&self) -> bool {
+        self.minimal
+    }
+    pub fn killer(&self) -> bool {
+        self.killer
+    }
+    pub fn samurai(&self) -> bool {
+        self.samurai
+    }
 }
 
 impl Solve {
-    pub fn puzzle(&self) -> game::Game {
-        self.puzzle
+    pub fn puzzle(&self) -> Result<Puzzle, error::Error> {
+        match &self.puzzle {
+            Puzzle::Single(game) => Ok(Puzzle::Single(Box::new(with_variants(
+                (**game).clone(),
+                &self.variant,
+            )?))),
+            Puzzle::Samurai(_) if !self.variant.is_empty() => {
+                error!("variants are not supported on Samurai puzzles")
+            }
+            Puzzle::Samurai(samurai) => Ok(Puzzle::Samurai(samurai.clone())),
+        }
+    }
+    pub fn backend(&self) -> game::Backend {
+        self.backend
+    }
+    pub fn explain(&self) -> bool {
+        self.explain
+    }
+}
+
+impl Hint {
+    pub fn puzzle(&self) -> Result<game::Game, error::Error> {
+        with_variants(self.puzzle.clone(), &self.variant)
+    }
+    pub fn tier(&self) -> game::Tier {
+        self.tier
+    }
+}
+
+impl Check {
+    pub fn puzzle(&self) -> Result<game::Game, error::Error> {
+        with_variants(self.puzzle.clone(), &self.variant)
+    }
+    pub fn board(&self) -> &game::Game {
+        &self.board
+    }
+}
+
+impl Transform {
+    pub fn puzzle(&self) -> Result<game::Game, error::Error> {
+        with_variants(self.puzzle.clone(), &self.variant)
+    }
+    pub fn transformation(&self) -> Option<game::Transformation> {
+        match &self.transformation {
+            Some(transformation) if self.inverse => Some(transformation.inverse()),
+            transformation => transformation.clone(),
+        }
+    }
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl Play {
+    pub fn puzzle(&self) -> Option<game::Game> {
+        self.puzzle.clone()
+    }
+    pub fn board(&self) -> game::Game {
+        empty_board(self.size, self.regions.as_ref())
+    }
+    pub fn difficulty(&self) -> game::Difficulty {
+        self.difficulty
+    }
+}
+
+impl Rate {
+    pub fn file(&self) -> Option<&std::path::Path> {
+        self.file.as_deref()
+    }
+    pub fn fingerprint(&self) -> bool {
+        self.fingerprint
+    }
+}
+
+// Jigsaw regions decide the size of the board when given
+fn empty_board(size: game::Size, regions: Option<&game::Regions>) -> game::Game {
+    match regions {
+        Some(regions) => game::Game::with_regions(
+            regions.clone(),
+            vec![game::Token::None; regions.size().cells()],
+        ),
+        None => game::Game::new_empty(size),
+    }
+}
+
+fn with_variants(game: game::Game, variants: &[game::Variant]) -> Result<game::Game, error::Error> {
+    if let Some(variant) = variants.iter().find(|variant| !variant.fits(game.size())) {
+        return error!("{variant} does not fit a {:?} board", game.size());
+    }
+    Ok(variants
+        .iter()
+        .fold(game, |game, variant| game.with_variant(*variant)))
+}
+
+// Boards are either comma separated numbers, as printed by the generator, or one character per
+// cell with letters after the digits. Zero and dots are empty cells. Sectors are inferred from the
+// number of cells unless a shape is given as a prefix, as in "2x3:", or a jigsaw region map is
+// given as a prefix with one label per cell. Killer cages and comma separated variants are given as
+// other prefixes. Anything up to a bracket is a heading, as in the lines printed by the generator
+pub fn to_game(value: &str) -> Result<game::Game, error::Error> {
+    let value = value.rsplit('[').next().unwrap_or_default();
+    let mut parts = value.split(':').map(str::trim).collect::<Vec<_>>();
+    let board = parts.pop().unwrap_or_default();
+    let tokens = to_tokens(board)?;
+
+    let mut regions = None;
+    let mut sectors = None;
+    let mut cages = None;
+    let mut variants = Vec::new();
+    for prefix in parts {
+        if let Ok(listed) = prefix
+            .split(',')
+            .map(to_variant)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            variants = listed;
+        } else if prefix.contains('=') {
+            cages = Some(prefix);
+        } else if prefix.chars().filter(char::is_ascii_alphanumeric).count() == tokens.len() {
+            regions = Some(to_regions(prefix)?);
+        } else if prefix.starts_with(|c: char| c.is_ascii_digit()) && prefix.contains(['x', 'X']) {
+            sectors = Some(prefix);
+        }
+    }
+
+    let size = match (sectors, &regions) {
+        (_, Some(regions)) => regions.size(),
+        (Some(sectors), None) => {
+            let size = to_sectors(sectors)?;
+            if size.cells() != tokens.len() {
+                return error!("{sectors} sectors need a {size} board");
+            }
+            size
+        }
+        (None, None) => match game::Size::from_cells(tokens.len()) {
+            Some(size) => size,
+            None => return error!("board must be square, from 4x4 up to 25x25"),
+        },
+    };
+
+    if let Some(token) = tokens.iter().find(|token| **token as usize > size.side()) {
+        return error!("token {token} is out of range for a {size} board");
+    }
+    let game = match regions {
+        Some(regions) => game::Game::with_regions(regions, tokens),
+        None => game::Game::with_tokens(size, tokens),
+    };
+    let game = with_variants(game, &variants)?;
+    match cages {
+        Some(cages) => Ok(game.with_cages(to_cages(cages, size)?)),
+        None => Ok(game),
+    }
+}
+
+// Boxed, so that subcommands taking two boards are not much larger than the others
+fn to_board(value: &str) -> Result<Box<game::Game>, error::Error> {
+    to_game(value).map(Box::new)
+}
+
+// Samurai puzzles carry a "Samurai:" prefix, as printed by the generator. Anything else is a single
+// grid
+fn to_puzzle(value: &str) -> Result<Puzzle, error::Error> {
+    let body = value.rsplit('[').next().unwrap_or_default();
+    if body
+        .split(':')
+        .any(|prefix| prefix.trim().eq_ignore_ascii_case("samurai"))
+    {
+        to_samurai(body).map(Puzzle::Samurai)
+    } else {
+        to_game(value).map(|game| Puzzle::Single(Box::new(game)))
+    }
+}
+
+// The tokens of the cells in reading order, skipping the gaps between the grids
+fn to_samurai(value: &str) -> Result<game::Samurai, error::Error> {
+    let board = value.rsplit(':').next().unwrap_or_default();
+    let tokens = to_tokens(board)?;
+    match game::Samurai::with_tokens(&tokens) {
+        Some(samurai) => Ok(samurai),
+        None => error!(
+            "Samurai puzzles need {} tokens from 0 to 9",
+            game::Samurai::cells().count()
+        ),
+    }
+}
+
+// Comma separated numbers, or one character per cell
+fn to_tokens(board: &str) -> Result<Vec<game::Token>, error::Error> {
+    if board.contains(',') {
+        board
+            .split(',')
+            .map(|token| {
+                let token = token.trim_matches(|c: char| c.is_whitespace() || c == ']');
+                match token {
+                    "." => Ok(game::Token::None),
+                    _ => match token.parse::<u8>() {
+                        Ok(value) if usize::from(value) <= 25 => Ok(game::Token::from(value)),
+                        _ => error!("invalid token '{token}'"),
+                    },
+                }
+            })
+            .collect()
+    } else {
+        board
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '.')
+            .map(|c| game::Token::from_char(c).map_or_else(|| error!("invalid token '{c}'"), Ok))
+            .collect()
+    }
+}
+
+// One label per cell, where cells with the same label make up a region. Labels are digits and
+// letters, or numbers when separated by commas
+fn to_regions(value: &str) -> Result<game::Regions, error::Error> {
+    let labels = if value.contains(',') {
+        value
+            .split(',')
+            .map(|label| match label.trim().parse::<usize>() {
+                Ok(label) => Ok(label),
+                Err(error) => error!(error; "invalid region label '{label}'"),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        value
+            .chars()
+            .filter_map(|c| c.to_digit(36))
+            .map(|label| label as usize)
+            .collect()
+    };
+
+    let Some(size) = game::Size::from_cells(labels.len()) else {
+        return error!("region map must be square, from 4x4 up to 25x25");
+    };
+    match game::Regions::new(size, &labels) {
+        Some(regions) => Ok(regions),
+        None => error!(
+            "region map must split the board into {side} connected regions of {side} cells",
+            side = size.side()
+        ),
     }
 }
 
-// Allowed because it is so much clearer
-#[allow(clippy::filter_map)]
-fn to_game(value: &str) -> Result<game::Game, error::Error> {
-    let clean_value = value
-        .chars()
-        .filter(|c| c.is_numeric())
-        .map(|c| (c as u8) - b'0')
-        .collect::<Vec<u8>>();
+// Either the side of the board, as in 6 or 6x6, optionally followed by the sector shape
+fn to_size(value: &str) -> Result<game::Size, error::Error> {
+    let mut parts = value.splitn(2, ':');
+    let board = parts.next().unwrap_or_default();
+    let width = match board.split_once(['x', 'X']) {
+        Some((rows, columns)) if rows == columns => rows,
+        Some(_) => return error!("board must be square"),
+        None => board,
+    };
+    let width = match width.trim().parse::<usize>() {
+        Ok(width) => width,
+        Err(error) => return error!(error; "invalid board size '{board}'"),
+    };
+
+    let size = match parts.next() {
+        Some(sectors) => to_sectors(sectors)?,
+        None => match game::Size::from_side(width) {
+            Some(size) => size,
+            None => return error!("no sectors fit a {width}x{width} board"),
+        },
+    };
 
-    if clean_value.len() == 81 {
-        let mut board = [0; 81];
-        board.copy_from_slice(&clean_value[0..81]);
-        Ok(game::Game::from(board))
+    if size.side() == width {
+        Ok(size)
     } else {
-        error!("board must be 9x9")
+        error!("{size:?} does not match a {width}x{width} board")
+    }
+}
+
+fn to_sectors(value: &str) -> Result<game::Size, error::Error> {
+    let shape = value
+        .split_once(['x', 'X'])
+        .and_then(|(rows, columns)| Some((rows.trim().parse().ok()?, columns.trim().parse().ok()?)))
+        .and_then(|(rows, columns)| game::Size::new(rows, columns));
+    match shape {
+        Some(size) => Ok(size),
+        None => error!("invalid sector shape '{value}', expected rows x columns up to 25 cells"),
+    }
+}
+
+// The sector shape, the tokens the tokens turn into and the row and column each line moves to,
+// counting from 1, followed by "T" when the board is transposed, as in 2x2:2,1,4,3:1,2,3,4:2,1,4,3:T
+fn to_transformation(value: &str) -> Result<game::Transformation, error::Error> {
+    let parts = value.split(':').map(str::trim).collect::<Vec<_>>();
+    let (transpose, parts) = match parts[..] {
+        [ref parts @ .., last] if last.eq_ignore_ascii_case("t") => (true, parts),
+        ref parts => (false, parts),
+    };
+    let [sectors, tokens, rows, columns] = parts[..] else {
+        return error!(
+            "invalid transformation '{value}', expected sectors, tokens, rows and columns"
+        );
+    };
+
+    let lines = |lines: &str| {
+        lines
+            .split(',')
+            .map(|line| match line.trim().parse::<usize>() {
+                Ok(line) if line > 0 => Ok(line - 1),
+                _ => error!("invalid line '{line}', expected a number from 1"),
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let size = to_sectors(sectors)?;
+    game::Transformation::new(
+        size,
+        to_tokens(tokens)?,
+        lines(rows)?,
+        lines(columns)?,
+        transpose,
+    )
+}
+
+// Cages separated by semicolons, each as its sum and its cells, as in 10=r1c1,r1c2;7=r1c3
+fn to_cages(value: &str, size: game::Size) -> Result<game::Cages, error::Error> {
+    let cages = value
+        .split(';')
+        .filter(|cage| !cage.trim().is_empty())
+        .map(|cage| {
+            let Some((sum, cells)) = cage.split_once('=') else {
+                return error!(
+                    "invalid cage '{cage}', expected a sum and its cells as in 10=r1c1,r1c2"
+                );
+            };
+            let sum = match sum.trim().parse::<usize>() {
+                Ok(sum) => sum,
+                Err(error) => return error!(error; "invalid cage sum '{sum}'"),
+            };
+            let cells = cells
+                .split(',')
+                .map(to_cell)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(game::Cage::new(sum, cells))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match game::Cages::new(size, cages) {
+        Some(cages) => Ok(cages),
+        None => error!(
+            "cages must be connected, fit on a {size} board without overlapping and have sums their cells can reach"
+        ),
+    }
+}
+
+fn to_cell(value: &str) -> Result<game::Cell, error::Error> {
+    let cell = value
+        .trim()
+        .to_lowercase()
+        .strip_prefix('r')
+        .and_then(|cell| cell.split_once('c'))
+        .and_then(|(row, column)| {
+            Some((row.parse::<usize>().ok()?, column.parse::<usize>().ok()?))
+        });
+    match cell {
+        Some((row, column)) if row > 0 && column > 0 => Ok(game::Cell::new(row - 1, column - 1)),
+        _ => error!("invalid cell '{value}', expected a row and a column as in r1c2"),
     }
 }
 
 fn to_difficulty(value: &str) -> Result<game::Difficulty, error::Error> {
     match value.to_uppercase().as_str() {
+        "BEGINNER" | "B" => Ok(game::Difficulty::Beginner),
         "EASY" | "E" => Ok(game::Difficulty::Easy),
         "MEDIUM" | "M" => Ok(game::Difficulty::Medium),
         "HARD" | "H" => Ok(game::Difficulty::Hard),
-        _ => error!("possible values are [easy, medium, hard]",),
+        "EXPERT" | "X" => Ok(game::Difficulty::Expert),
+        "DIABOLICAL" | "D" => Ok(game::Difficulty::Diabolical),
+        _ => error!("possible values are [beginner, easy, medium, hard, expert, diabolical]",),
+    }
+}
+
+fn to_symmetry(value: &str) -> Result<game::Symmetry, error::Error> {
+    match value.to_uppercase().as_str() {
+        "NONE" => Ok(game::Symmetry::None),
+        "ROT180" => Ok(game::Symmetry::Rot180),
+        "ROT90" => Ok(game::Symmetry::Rot90),
+        "HORIZONTAL" => Ok(game::Symmetry::Horizontal),
+        "VERTICAL" => Ok(game::Symmetry::Vertical),
+        "DIAGONAL" => Ok(game::Symmetry::Diagonal),
+        "ANTI-DIAGONAL" => Ok(game::Symmetry::AntiDiagonal),
+        "DIHEDRAL" | "FULL" => Ok(game::Symmetry::Dihedral),
+        _ => error!(
+            "possible values are [none, rot180, rot90, horizontal, vertical, diagonal, anti-diagonal, dihedral]",
+        ),
+    }
+}
+
+fn to_variant(value: &str) -> Result<game::Variant, error::Error> {
+    match value.trim().to_uppercase().as_str() {
+        "X-SUDOKU" | "X" | "DIAGONAL" => Ok(game::Variant::Diagonal),
+        "WINDOKU" | "HYPER" => Ok(game::Variant::Windoku),
+        "ANTI-KNIGHT" | "KNIGHT" => Ok(game::Variant::AntiKnight),
+        "ANTI-KING" | "KING" => Ok(game::Variant::AntiKing),
+        _ => error!("possible values are [x-sudoku, windoku, anti-knight, anti-king]"),
+    }
+}
+
+fn to_backend(value: &str) -> Result<game::Backend, error::Error> {
+    match value.to_uppercase().as_str() {
+        "BACKTRACKING" | "B" => Ok(game::Backend::Backtracking),
+        "DANCING-LINKS" | "DLX" | "D" => Ok(game::Backend::DancingLinks),
+        _ => error!("possible values are [backtracking, dancing-links]",),
+    }
+}
+
+fn to_tier(value: &str) -> Result<game::Tier, error::Error> {
+    match value.to_uppercase().as_str() {
+        "NUDGE" | "N" => Ok(game::Tier::Nudge),
+        "TECHNIQUE" | "T" => Ok(game::Tier::Technique),
+        "ANSWER" | "A" => Ok(game::Tier::Answer),
+        _ => error!("possible values are [nudge, technique, answer]",),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Cell, Size, Token, Variant};
+
+    #[test]
+    fn to_game() {
+        let standard = "1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,9";
+        let game = super::to_game(standard).unwrap();
+        assert_eq!(game.size(), Size::STANDARD);
+        assert_eq!(game.get(Cell::new(8, 8)), Token::Nine);
+
+        let game = super::to_game(&format!("Medium: [{standard}]")).unwrap();
+        assert_eq!(game.get(Cell::new(0, 0)), Token::One);
+
+        let game = super::to_game("12..\n..1.\n.3..\n...4").unwrap();
+        assert_eq!(game.size(), Size::new(2, 2).unwrap());
+        assert_eq!(game.get(Cell::new(3, 3)), Token::Four);
+
+        let mut hex = "0".repeat(256);
+        hex.replace_range(0..2, "aG");
+        let game = super::to_game(&hex).unwrap();
+        assert_eq!(game.size(), Size::new(4, 4).unwrap());
+        assert_eq!(game.get(Cell::new(0, 0)), Token::Ten);
+        assert_eq!(game.get(Cell::new(0, 1)), Token::Sixteen);
+
+        let game = super::to_game(&format!("3x2:{}", "0".repeat(36))).unwrap();
+        assert_eq!(game.size(), Size::new(3, 2).unwrap());
+        assert_eq!(format!("{game:?}"), format!("3x2:{}0", "0,".repeat(35)));
+        assert_eq!(
+            super::to_game(&format!("{game:?}")).unwrap().size(),
+            game.size()
+        );
+
+        assert!(super::to_game("1234").is_err());
+        assert!(super::to_game(&"7".repeat(16)).is_err());
+        assert!(super::to_game(&format!("2x2:{}", "0".repeat(36))).is_err());
+        assert!(super::to_game(&format!("{standard},x")).is_err());
+    }
+
+    #[test]
+    fn to_regions() {
+        let labels = "AABB\nAABB\nCCDD\nCCDD";
+        assert!(super::to_regions(labels).unwrap().is_standard());
+
+        let regions = super::to_regions("0001\n0211\n2231\n2333").unwrap();
+        assert!(!regions.is_standard());
+        assert_eq!(regions.region(Cell::new(1, 1)), 2);
+
+        let game = super::to_game(&format!("{regions}:{}", "0".repeat(16))).unwrap();
+        assert_eq!(game.regions(), &regions);
+        assert_eq!(
+            super::to_game(&format!("{game:?}")).unwrap().regions(),
+            &regions
+        );
+
+        assert!(super::to_regions("0011,0011,2233,2233,").is_err());
+        assert!(super::to_regions("0101101022332233").is_err());
+        assert!(super::to_game(&format!("0101101022332233:{}", "0".repeat(16))).is_err());
+    }
+
+    #[test]
+    fn to_cages() {
+        let size = Size::new(2, 2).unwrap();
+        let cages = super::to_cages("3=r1c1,r1c2; 7=R1C3,R1C4", size).unwrap();
+        assert_eq!(cages.iter().count(), 2);
+        assert_eq!(cages.cage(Cell::new(0, 3)).unwrap().sum(), 7);
+        assert_eq!(cages.cage(Cell::new(1, 0)), None);
+
+        let game = super::to_game(&format!("Expert: [{cages}:{}]", "0,".repeat(15) + "0")).unwrap();
+        assert_eq!(game.cages(), Some(&cages));
+        let game = super::to_game(&format!("2x2:{cages}:{}", ".".repeat(16))).unwrap();
+        assert_eq!(
+            super::to_game(&format!("{game:?}")).unwrap().cages(),
+            Some(&cages)
+        );
+
+        assert!(super::to_cages("3=r1c1,r2c2", size).is_err());
+        assert!(super::to_cages("3=r1c1,r1c2;4=r1c2,r1c3", size).is_err());
+        assert!(super::to_cages("30=r1c1,r1c2", size).is_err());
+        assert!(super::to_cages("3=r0c1,r1c2", size).is_err());
+        assert!(super::to_cages("3=r1c1;x", size).is_err());
+        assert!(super::to_cages("3=r5c1", size).is_err());
+    }
+
+    #[test]
+    fn to_variant() {
+        assert_eq!(super::to_variant("x").unwrap(), Variant::Diagonal);
+        assert_eq!(super::to_variant("Windoku").unwrap(), Variant::Windoku);
+        assert_eq!(
+            super::to_variant("anti-knight").unwrap(),
+            Variant::AntiKnight
+        );
+        assert_eq!(super::to_variant("KING").unwrap(), Variant::AntiKing);
+        assert!(super::to_variant("jigsaw").is_err());
+
+        let game = super::to_game(&format!("x-sudoku, hyper:{}", ".".repeat(81))).unwrap();
+        assert_eq!(game.variants(), [Variant::Diagonal, Variant::Windoku]);
+        assert_eq!(
+            super::to_game(&format!("Hard: [{game:?}]"))
+                .unwrap()
+                .variants(),
+            game.variants()
+        );
+        assert!(super::to_game(&format!("windoku:{}", ".".repeat(36))).is_err());
+    }
+
+    #[test]
+    fn to_puzzle() {
+        let board = ".".repeat(80) + "1";
+        assert!(matches!(
+            super::to_puzzle(&board),
+            Ok(super::Puzzle::Single(_))
+        ));
+
+        let samurai = format!("Hard: [Samurai:{}]", "0,".repeat(368) + "7");
+        match super::to_puzzle(&samurai) {
+            Ok(super::Puzzle::Samurai(samurai)) => {
+                assert_eq!(samurai.get(Cell::new(20, 20)), Token::Seven);
+                assert_eq!(
+                    super::to_samurai(&format!("{samurai:?}")).unwrap().clues(),
+                    1
+                );
+            }
+            _ => panic!("expected a Samurai puzzle"),
+        }
+        assert!(super::to_puzzle(&format!("samurai:{board}")).is_err());
+    }
+
+    #[test]
+    fn to_size() {
+        assert_eq!(super::to_size("9").unwrap(), Size::STANDARD);
+        assert_eq!(super::to_size("6x6").unwrap(), Size::new(2, 3).unwrap());
+        assert_eq!(super::to_size("6x6:3x2").unwrap(), Size::new(3, 2).unwrap());
+        assert_eq!(super::to_size("12x12").unwrap(), Size::new(3, 4).unwrap());
+        assert_eq!(super::to_size("25").unwrap(), Size::new(5, 5).unwrap());
+
+        assert!(super::to_size("7").is_err());
+        assert!(super::to_size("6x9").is_err());
+        assert!(super::to_size("6x6:2x2").is_err());
+        assert!(super::to_size("36").is_err());
+    }
+
+    #[test]
+    fn to_transformation() {
+        let transformation = super::to_transformation("2x2:2,1,4,3:1,2,3,4:2,1,4,3:T").unwrap();
+        assert!(transformation.is_transposed());
+        assert_eq!(transformation.token(Token::One), Token::Two);
+        assert_eq!(transformation.cell(Cell::new(0, 0)), Cell::new(1, 0));
+        assert_eq!(
+            super::to_transformation(&transformation.inverse().to_string()).unwrap(),
+            transformation.inverse()
+        );
+        assert!(
+            !super::to_transformation("2x3:1,2,3,4,5,6:1,2,3,4,5,6:1,2,3,4,5,6")
+                .unwrap()
+                .is_transposed()
+        );
+
+        assert!(super::to_transformation("2x2:2,1,4,3:1,2,3,4").is_err());
+        assert!(super::to_transformation("2x2:2,1,4,3:0,1,2,3:1,2,3,4").is_err());
+        // Rows 2 and 3 are in different bands
+        assert!(super::to_transformation("2x2:2,1,4,3:1,3,2,4:1,2,3,4").is_err());
     }
 }
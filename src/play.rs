@@ -0,0 +1,411 @@
+use std::convert::TryFrom;
+use std::io::Write;
+
+use crossterm::{cursor, event, execute, queue, style, terminal};
+
+use crate::game::{Cell, Game, History, Token};
+
+static HELP: &str =
+    "arrows/hjkl: move | 1-9/a-p: place | 0/space/backspace: erase | u/r: undo/redo | x: back to the first mistake | q: quit";
+
+pub fn play(puzzle: Game) -> crossterm::Result<()> {
+    let mut stdout = std::io::stdout();
+
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen)?;
+
+    let result = run(&mut stdout, puzzle);
+
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+fn run(stdout: &mut std::io::Stdout, puzzle: Game) -> crossterm::Result<()> {
+    let mut state = State::new(puzzle);
+
+    loop {
+        draw(stdout, &state)?;
+
+        if let event::Event::Key(key) = event::read()? {
+            match state.handle(key) {
+                Action::Continue => {}
+                Action::Quit => return Ok(()),
+                Action::Solved => break,
+            }
+        }
+    }
+
+    draw_solved(stdout, &state)?;
+    loop {
+        if let event::Event::Key(_) = event::read()? {
+            return Ok(());
+        }
+    }
+}
+
+fn draw(stdout: &mut std::io::Stdout, state: &State) -> crossterm::Result<()> {
+    queue!(
+        stdout,
+        cursor::Hide,
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    let lines = draw_board(stdout, state.history.game())?;
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, lines + 1),
+        style::Print(HELP),
+        cursor::MoveTo(0, lines + 2),
+        style::Print(state.message.unwrap_or_default()),
+    )?;
+
+    let (x, y) = screen_position(state.history.game(), state.row, state.column);
+    queue!(stdout, cursor::MoveTo(x, y), cursor::Show)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn draw_solved(stdout: &mut std::io::Stdout, state: &State) -> crossterm::Result<()> {
+    queue!(
+        stdout,
+        cursor::Hide,
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    let lines = draw_board(stdout, state.history.game())?;
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, lines + 1),
+        style::Print("Solved! Press any key to exit"),
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn draw_board(stdout: &mut std::io::Stdout, game: &Game) -> crossterm::Result<u16> {
+    // Raw mode does not return the carriage on a new line, so each line is placed explicitly
+    let mut lines = 0;
+    // The alternate form sets the givens apart
+    for line in format!("{game:#}").lines() {
+        queue!(stdout, cursor::MoveTo(0, lines), style::Print(line))?;
+        lines += 1;
+    }
+    Ok(lines)
+}
+
+// Maps a cell into the character grid drawn by `Display for Game`, where jigsaw boards have a line
+// of borders after every row. Killer boards also have a line of sums before every row, and cells as
+// wide as the largest sum with the token on the right
+fn screen_position(game: &Game, row: usize, column: usize) -> (u16, u16) {
+    let (x, y) = if let Some(cages) = game.cages() {
+        let width = cages.width();
+        (column * (width + 1) + width, row * 3 + 2)
+    } else if game.regions().is_standard() {
+        (1 + column * 2, 1 + row + row / game.size().sector_rows())
+    } else {
+        (1 + column * 2, 1 + row * 2)
+    };
+    (
+        u16::try_from(x).unwrap_or(u16::MAX),
+        u16::try_from(y).unwrap_or(u16::MAX),
+    )
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Action {
+    Continue,
+    Quit,
+    Solved,
+}
+
+struct State {
+    history: History,
+    row: usize,
+    column: usize,
+    message: Option<&'static str>,
+}
+
+impl State {
+    // Whatever the puzzle holds is given, however it was made
+    fn new(puzzle: Game) -> Self {
+        Self {
+            history: History::new(puzzle.with_givens()),
+            row: 0,
+            column: 0,
+            message: None,
+        }
+    }
+
+    fn handle(&mut self, key: event::KeyEvent) -> Action {
+        use event::{KeyCode, KeyModifiers};
+
+        self.message = None;
+        let last = self.history.game().size().side() - 1;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            // Letters are tokens on the larger boards, so they only move the cursor otherwise
+            KeyCode::Char(character) if self.token(character).is_some() => {
+                self.place(self.token(character).unwrap_or(Token::None))
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.column = self.column.saturating_sub(1);
+                Action::Continue
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.row = std::cmp::min(self.row + 1, last);
+                Action::Continue
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.row = self.row.saturating_sub(1);
+                Action::Continue
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.column = std::cmp::min(self.column + 1, last);
+                Action::Continue
+            }
+            KeyCode::Char('0' | ' ') | KeyCode::Backspace | KeyCode::Delete => {
+                self.place(Token::None)
+            }
+            KeyCode::Char('u') => {
+                if !self.history.undo() {
+                    self.message = Some("Nothing to undo");
+                }
+                Action::Continue
+            }
+            KeyCode::Char('x') => {
+                self.rewind();
+                Action::Continue
+            }
+            KeyCode::Char('r') => {
+                if !self.history.redo() {
+                    self.message = Some("Nothing to redo");
+                }
+                self.status()
+            }
+            _ => Action::Continue,
+        }
+    }
+
+    fn token(&self, character: char) -> Option<Token> {
+        Token::from_char(character).filter(|token| {
+            *token != Token::None && *token as usize <= self.history.game().size().side()
+        })
+    }
+
+    fn place(&mut self, token: Token) -> Action {
+        let cell = Cell::new(self.row, self.column);
+        match self.history.set(cell, token) {
+            Ok(true) => {}
+            Ok(false) => self.message = Some("This conflicts with another cell"),
+            Err(_) => {
+                self.message = Some("This cell is part of the puzzle");
+                return Action::Continue;
+            }
+        }
+        self.status()
+    }
+
+    // Goes back to just before the move that put the earliest wrong token still on the board
+    fn rewind(&mut self) {
+        self.message = match self.history.first_mistake() {
+            Ok(Some(point)) => {
+                self.history.goto(point);
+                self.history.undo();
+                Some("Went back to before the first mistake")
+            }
+            Ok(None) => Some("No mistakes so far"),
+            Err(_) => Some("This puzzle has no single solution to check against"),
+        };
+    }
+
+    fn status(&self) -> Action {
+        if self.history.game().is_solved() {
+            Action::Solved
+        } else {
+            Action::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Action, State};
+    use crate::game::{Cage, Cages, Cell, Game, Regions, Size, Token};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn screen_position() {
+        let game = Game::new_empty(Size::STANDARD);
+        assert_eq!(super::screen_position(&game, 0, 0), (1, 1));
+        assert_eq!(super::screen_position(&game, 2, 2), (5, 3));
+        assert_eq!(super::screen_position(&game, 3, 3), (7, 5));
+        assert_eq!(super::screen_position(&game, 8, 8), (17, 11));
+
+        let game = Game::new_empty(Size::new(2, 3).unwrap());
+        assert_eq!(super::screen_position(&game, 2, 3), (7, 4));
+        assert_eq!(super::screen_position(&game, 5, 5), (11, 8));
+
+        let regions = Regions::new(Size::STANDARD, &crate::game::jigsaw_labels()).unwrap();
+        let game = Game::with_regions(regions, vec![Token::None; 81]);
+        assert_eq!(super::screen_position(&game, 0, 0), (1, 1));
+        assert_eq!(super::screen_position(&game, 4, 2), (5, 9));
+
+        let size = Size::new(2, 2).unwrap();
+        let cage = Cage::new(
+            10,
+            vec![
+                Cell::new(0, 0),
+                Cell::new(0, 1),
+                Cell::new(1, 0),
+                Cell::new(1, 1),
+            ],
+        );
+        let game = Game::new_empty(size).with_cages(Cages::new(size, vec![cage]).unwrap());
+        assert_eq!(super::screen_position(&game, 0, 0), (2, 2));
+        assert_eq!(super::screen_position(&game, 3, 3), (11, 11));
+    }
+
+    #[test]
+    fn movement() {
+        let mut state = State::new(Game::new_empty(Size::STANDARD));
+
+        assert_eq!(state.handle(key(KeyCode::Up)), Action::Continue);
+        assert_eq!(state.handle(key(KeyCode::Char('h'))), Action::Continue);
+        assert_eq!((state.row, state.column), (0, 0));
+
+        for _ in 0..10 {
+            state.handle(key(KeyCode::Down));
+            state.handle(key(KeyCode::Char('l')));
+        }
+        assert_eq!((state.row, state.column), (8, 8));
+
+        state.handle(key(KeyCode::Char('k')));
+        state.handle(key(KeyCode::Left));
+        assert_eq!((state.row, state.column), (7, 7));
+    }
+
+    #[test]
+    fn letters() {
+        let mut state = State::new(Game::new_empty(Size::new(4, 5).unwrap()));
+
+        // Letters that are tokens on this board place them instead of moving
+        state.handle(key(KeyCode::Char('g')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::Sixteen);
+        state.handle(key(KeyCode::Char('j')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::Nineteen);
+        assert_eq!((state.row, state.column), (0, 0));
+
+        let mut state = State::new(Game::new_empty(Size::new(2, 3).unwrap()));
+        state.handle(key(KeyCode::Char('7')));
+        state.handle(key(KeyCode::Char('a')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::None);
+        state.handle(key(KeyCode::Char('j')));
+        assert_eq!((state.row, state.column), (1, 0));
+    }
+
+    #[test]
+    fn placement() {
+        let mut puzzle = Game::new_empty(Size::STANDARD);
+        assert!(puzzle.set(Cell::new(0, 1), Token::Five).unwrap());
+        let mut state = State::new(puzzle);
+
+        state.handle(key(KeyCode::Char('3')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::Three);
+        assert!(state.message.is_none());
+
+        state.handle(key(KeyCode::Backspace));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::None);
+
+        state.handle(key(KeyCode::Char('5')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::Five);
+        assert!(state.message.is_some());
+
+        state.handle(key(KeyCode::Right));
+        state.handle(key(KeyCode::Char('1')));
+        assert_eq!(state.history.game().get(Cell::new(0, 1)), Token::Five);
+        assert!(state.message.is_some());
+    }
+
+    #[test]
+    fn undo() {
+        let mut state = State::new(Game::new_empty(Size::STANDARD));
+        state.handle(key(KeyCode::Char('u')));
+        assert!(state.message.is_some());
+
+        state.handle(key(KeyCode::Char('3')));
+        state.handle(key(KeyCode::Right));
+        state.handle(key(KeyCode::Char('4')));
+        state.handle(key(KeyCode::Char('u')));
+        state.handle(key(KeyCode::Char('u')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::None);
+        state.handle(key(KeyCode::Char('r')));
+        assert_eq!(state.history.game().get(Cell::new(0, 0)), Token::Three);
+        assert_eq!(state.history.game().get(Cell::new(0, 1)), Token::None);
+        assert!(state.message.is_none());
+    }
+
+    #[test]
+    fn rewind() {
+        #[rustfmt::skip]
+        let puzzle = Game::from([
+            1, 0, 0, 0, 3, 5, 7, 4, 9,
+            3, 5, 4, 7, 6, 9, 1, 8, 2,
+            9, 2, 7, 1, 4, 8, 6, 5, 3,
+            4, 9, 5, 6, 8, 1, 2, 3, 7,
+            6, 1, 3, 4, 0, 7, 8, 9, 5,
+            8, 7, 2, 9, 5, 3, 4, 6, 1,
+            7, 3, 1, 8, 9, 4, 5, 2, 6,
+            5, 4, 6, 3, 1, 2, 9, 7, 8,
+            2, 8, 9, 5, 7, 6, 3, 1, 4,
+        ]);
+        let mut state = State::new(puzzle);
+        state.handle(key(KeyCode::Char('x')));
+        assert_eq!(state.message, Some("No mistakes so far"));
+
+        // The first row is 1, 6, 8 and 2 in the solution, so both of the last two are mistakes
+        state.handle(key(KeyCode::Right));
+        state.handle(key(KeyCode::Char('6')));
+        state.handle(key(KeyCode::Right));
+        state.handle(key(KeyCode::Char('2')));
+        state.handle(key(KeyCode::Right));
+        state.handle(key(KeyCode::Char('8')));
+        state.handle(key(KeyCode::Char('x')));
+        assert_eq!(state.history.game().get(Cell::new(0, 1)), Token::Six);
+        assert_eq!(state.history.game().get(Cell::new(0, 2)), Token::None);
+        assert_eq!(state.history.game().get(Cell::new(0, 3)), Token::None);
+    }
+
+    #[test]
+    fn completion() {
+        #[rustfmt::skip]
+        let puzzle = Game::from([
+            1, 6, 8, 2, 3, 5, 7, 4, 9,
+            3, 5, 4, 7, 6, 9, 1, 8, 2,
+            9, 2, 7, 1, 4, 8, 6, 5, 3,
+            4, 9, 5, 6, 8, 1, 2, 3, 7,
+            6, 1, 3, 4, 0, 7, 8, 9, 5,
+            8, 7, 2, 9, 5, 3, 4, 6, 1,
+            7, 3, 1, 8, 9, 4, 5, 2, 6,
+            5, 4, 6, 3, 1, 2, 9, 7, 8,
+            2, 8, 9, 5, 7, 6, 3, 1, 4,
+        ]);
+
+        let mut state = State::new(puzzle);
+        for _ in 0..4 {
+            state.handle(key(KeyCode::Down));
+            state.handle(key(KeyCode::Right));
+        }
+
+        assert_eq!(state.handle(key(KeyCode::Char('7'))), Action::Continue);
+        assert_eq!(state.handle(key(KeyCode::Char('2'))), Action::Solved);
+        assert_eq!(state.handle(key(KeyCode::Char('q'))), Action::Quit);
+    }
+}